@@ -1,8 +1,14 @@
 use std::fmt;
+use crate::solver::{self, SolveOutcome};
+use crate::error::{self, SudokuError};
+use crate::regions::Regions;
+use crate::rules::{ExtraPeers, Rules};
 
 #[derive(Debug)]
 pub struct Sudoku {
     grid: [[u8; 9]; 9],
+    regions: Regions,
+    extra_peers: ExtraPeers,
 }
 
 #[allow(clippy::new_without_default)]
@@ -10,35 +16,45 @@ impl Sudoku {
     pub fn new() -> Sudoku {
         Sudoku {
             grid: [[0u8; 9]; 9],
+            regions: Regions::standard(),
+            extra_peers: ExtraPeers::default(),
         }
     }
 
-    pub fn from(grid: [[u8; 9]; 9]) -> Result<Sudoku, &'static str> {
+    pub fn from(grid: [[u8; 9]; 9]) -> Result<Sudoku, SudokuError> {
+        let mut new_sudoku = Sudoku::new();
+        new_sudoku.set_grid(grid)?;
+        Ok(new_sudoku)
+    }
+
+    pub fn from_rules(grid: [[u8; 9]; 9], rules: &Rules) -> Result<Sudoku, SudokuError> {
         let mut new_sudoku = Sudoku {
             grid: [[0u8; 9]; 9],
+            regions: rules.regions().clone(),
+            extra_peers: rules.all_extra_peers().clone(),
         };
         new_sudoku.set_grid(grid)?;
         Ok(new_sudoku)
     }
 
-    fn restrain_index(r: usize, c: usize) -> Result<(), &'static str> {
+    fn restrain_index(r: usize, c: usize) -> Result<(), SudokuError> {
         if (0..9).contains(&r) && (0..9).contains(&c) { Ok(()) }
-        else { Err("Index greater than 9") }
+        else { Err(SudokuError::IndexOutOfRange { row: r, col: c, size: 9 }) }
     }
 
-    pub fn set(&mut self, r: usize, c: usize, n: u8) -> Result<(), &'static str> {
+    pub fn set(&mut self, r: usize, c: usize, n: u8) -> Result<(), SudokuError> {
         Sudoku::restrain_index(r, c)?;
-        if !(1..=9).contains(&n) { return Err("Value is not in range [1..=9]"); }
+        if !(1..=9).contains(&n) { return Err(SudokuError::ValueOutOfRange { row: r, col: c, value: n, max: 9 }); }
         self.grid[r][c] = n;
         Ok(())
     }
 
-    pub fn get(&self, r: usize, c: usize) -> Result<u8, &'static str> {
+    pub fn get(&self, r: usize, c: usize) -> Result<u8, SudokuError> {
         Sudoku::restrain_index(r, c)?;
         Ok(self.grid[r][c])
     }
 
-    pub fn clear(&mut self, r: usize, c: usize) -> Result<(), &'static str> {
+    pub fn clear(&mut self, r: usize, c: usize) -> Result<(), SudokuError> {
         Sudoku::restrain_index(r, c)?;
         self.grid[r][c] = 0;
         Ok(())
@@ -48,7 +64,7 @@ impl Sudoku {
         self.grid = [[0u8; 9]; 9];
     }
 
-    pub fn is_valid(&self, r: usize, c: usize) -> Result<bool, &'static str> {
+    pub fn is_valid(&self, r: usize, c: usize) -> Result<bool, SudokuError> {
         Sudoku::restrain_index(r, c)?;
         let this_num = self.get(r, c)?;
         if this_num == 0 { return Ok(true); }
@@ -64,13 +80,13 @@ impl Sudoku {
             if self.get(rr, c)? == this_num { return Ok(false); }
         }
         // check zone
-        let zone_r = r / 3;
-        let zone_c = c / 3;
-        for rr in (3 * zone_r)..((zone_r + 1) * 3) {
-            for cc in (3 * zone_c)..((zone_c + 1) * 3) {
-                if rr == r && cc == c { continue; }
-                if self.get(rr, cc)? == this_num { return Ok(false);}
-            }
+        for &(rr, cc) in self.regions.cells(self.regions.region(r, c)) {
+            if rr == r && cc == c { continue; }
+            if self.get(rr, cc)? == this_num { return Ok(false);}
+        }
+        // check extra houses
+        for &(rr, cc) in self.extra_peers.of(r, c) {
+            if self.get(rr, cc)? == this_num { return Ok(false); }
         }
         // if all above checks pass then the cell is valid
         Ok(true)
@@ -86,12 +102,10 @@ impl Sudoku {
         true
     }
 
-    pub fn set_grid(&mut self, grid: [[u8; 9]; 9]) -> Result<(), &'static str> {
-        for r in grid {
-            for v in r {
-                if !(0..=9).contains(&v) { return Err("grid contains value outside [0..=9]") }
-            }
-        }
+    pub fn set_grid(&mut self, grid: [[u8; 9]; 9]) -> Result<(), SudokuError> {
+        error::validate_with(&grid, |a, b| {
+            self.regions.sees(a, b) || self.extra_peers.of(a.0, a.1).contains(&b)
+        })?;
         self.grid = grid;
         Ok(())
     }
@@ -127,6 +141,33 @@ impl Sudoku {
     }
 }
 
+pub struct Backtrack;
+
+impl solver::Solver for Backtrack {
+    fn name(&self) -> &'static str {
+        "backtracking"
+    }
+
+    fn solve(&self, grid: &[[u8; 9]; 9]) -> SolveOutcome {
+        let mut sudoku = match Sudoku::from(*grid) {
+            Ok(sudoku) => sudoku,
+            Err(err) => return SolveOutcome::Invalid(err),
+        };
+        if sudoku.solve() { SolveOutcome::Solved(*sudoku.grid()) }
+        else { SolveOutcome::Unsolvable }
+    }
+
+    fn solve_with(&self, grid: &[[u8; 9]; 9], rules: &Rules) -> SolveOutcome {
+        if !self.supports(rules) { return solver::UNSUPPORTED; }
+        let mut sudoku = match Sudoku::from_rules(*grid, rules) {
+            Ok(sudoku) => sudoku,
+            Err(err) => return SolveOutcome::Invalid(err),
+        };
+        if sudoku.solve() { SolveOutcome::Solved(*sudoku.grid()) }
+        else { SolveOutcome::Unsolvable }
+    }
+}
+
 impl fmt::Display for Sudoku {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut s = String::new();
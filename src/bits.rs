@@ -1,156 +1,234 @@
 use std::fmt;
+use crate::solver::{self, SolveOutcome};
+use crate::error::{self, SudokuError};
+use crate::rules::{ExtraPeers, Rules};
 
 #[derive(Clone, Copy)]
 #[derive(Debug)]
-pub struct BitSet(u16);
-impl BitSet {
+pub struct BitSetN<const N: usize>(u32);
+
+pub type BitSet = BitSetN<9>;
+
+impl<const N: usize> BitSetN<N> {
+    const FITS: () = assert!(N < 32, "values must fit in a u32");
+
     pub fn contains(&self, x: u8) -> bool {
-        if !(1..=9).contains(&x) { return false; }
+        if !(1..=N).contains(&(x as usize)) { return false; }
         (self.0 & (1 << x)) != 0
     }
     
     pub fn insert(&mut self, x: u8) -> bool {
-        if !(1..=9).contains(&x) { return false; }
+        if !(1..=N).contains(&(x as usize)) { return false; }
         self.0 |= 1 << x;
         true
     }
 
     pub fn remove(&mut self, x: u8) -> bool {
-        if !(1..=9).contains(&x) { return false; }
+        if !(1..=N).contains(&(x as usize)) { return false; }
         self.0 &= !(1 << x);
         true
     }
     
     #[allow(clippy::new_without_default)]
-    pub fn new() -> BitSet {
-        BitSet(0u16)
+    pub fn new() -> BitSetN<N> {
+        BitSetN(0u32)
     }
 
-    pub fn full() -> BitSet {
-        BitSet(0b1111111110)
+    pub fn full() -> BitSetN<N> {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::FITS;
+        BitSetN((u32::MAX >> (31 - N)) & !1) // bits 1..=N
     }
 }
 
-#[derive(Debug)]
-pub struct Sudoku {
-    grid: [[u8; 9]; 9],
-    r_sets: [BitSet; 9],
-    c_sets: [BitSet; 9],
-    z_sets: [[BitSet; 3]; 3],
+// An N x N grid split into boxes of BH rows by BW columns, so N = BH * BW.
+// Boxes are numbered row by row, `BH` of them side by side, unless a 9x9
+// grid is given jigsaw regions instead. Extra houses are checked cell by cell.
+#[derive(Debug, Clone)]
+pub struct SudokuN<const N: usize, const BH: usize, const BW: usize> {
+    grid: [[u8; N]; N],
+    r_sets: [BitSetN<N>; N],
+    c_sets: [BitSetN<N>; N],
+    z_sets: [BitSetN<N>; N],
+    // the zone of every cell
+    zones: [[u8; N]; N],
+    extra_peers: ExtraPeers,
 }
 
+pub type Sudoku = SudokuN<9, 3, 3>;
+pub type Sudoku4 = SudokuN<4, 2, 2>;
+pub type Sudoku6 = SudokuN<6, 2, 3>;
+pub type Sudoku12 = SudokuN<12, 3, 4>;
+pub type Sudoku16 = SudokuN<16, 4, 4>;
+pub type Sudoku25 = SudokuN<25, 5, 5>;
+
 #[allow(clippy::new_without_default)]
-impl Sudoku {
-    pub fn new() -> Sudoku {
-        let r_sets: [BitSet; 9] = vec![Sudoku::init_set(); 9]
-            .try_into().unwrap();
-        let c_sets = vec![Sudoku::init_set(); 9]
-            .try_into().unwrap();
-        let z_sets: [[BitSet; 3]; 3] = vec![vec![Sudoku::init_set(); 3]; 3]
-            .into_iter().map(|v| v.try_into().unwrap())
-            .collect::<Vec<_>>()
-            .try_into().unwrap();
-
-        Sudoku {
-            grid: [[0u8; 9]; 9],
-            r_sets,
-            c_sets,
-            z_sets,
+impl<const N: usize, const BH: usize, const BW: usize> SudokuN<N, BH, BW> {
+    const BOXES: () = assert!(BH * BW == N, "boxes must tile the grid");
+
+    pub fn new() -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::BOXES;
+        SudokuN {
+            grid: [[0u8; N]; N],
+            r_sets: [Self::init_set(); N],
+            c_sets: [Self::init_set(); N],
+            z_sets: [Self::init_set(); N],
+            zones: std::array::from_fn(|r| std::array::from_fn(|c| ((r / BH) * BH + c / BW) as u8)),
+            extra_peers: ExtraPeers::default(),
         }
     }
 
-    pub fn from(grid: [[u8; 9]; 9]) -> Result<Sudoku, &'static str> {
-        let mut new_sudoku = Sudoku {
-            grid: [[0u8; 9]; 9],
-            ..Sudoku::new()
+    pub fn from(grid: [[u8; N]; N]) -> Result<Self, SudokuError> {
+        let mut new_sudoku = SudokuN {
+            grid: [[0u8; N]; N],
+            ..Self::new()
         };
         new_sudoku.set_grid(grid)?;
         Ok(new_sudoku)
     }
 
-    pub fn set(&mut self, r: usize, c: usize, n: u8) -> Result<bool, &'static str> {
+    pub fn set(&mut self, r: usize, c: usize, n: u8) -> Result<bool, SudokuError> {
         if n == 0 { self.clear(r, c)?; return Ok(true); }
-        if !(1..=9).contains(&n) { return Err("Value is not in range 1..=9, was {n}"); }
-        if !self.is_valid(r, c, n)? { return Ok(false); }
-        self.grid[r][c] = n;
-        self.r_sets[r].remove(n);
-        self.c_sets[c].remove(n);
-        self.z_sets[r / 3][c / 3].remove(n);
-        
+        Self::restrain_index(r, c)?;
+        if !(1..=N).contains(&(n as usize)) { return Err(SudokuError::ValueOutOfRange { row: r, col: c, value: n, max: N as u8 }); }
+        let z = self.zones[r][c] as usize;
+        if !self.fits::<true>(r, c, z, n) { return Ok(false); }
+        self.place(r, c, z, n);
         Ok(true)
     }
 
-    pub fn get(&self, r: usize, c: usize) -> Result<u8, &'static str> {
-        Sudoku::restrain_index(r, c)?;
+    pub fn get(&self, r: usize, c: usize) -> Result<u8, SudokuError> {
+        Self::restrain_index(r, c)?;
         Ok(self.grid[r][c])
     }
 
-    pub fn clear(&mut self, r: usize, c: usize) -> Result<(), &'static str> {
+    pub fn clear(&mut self, r: usize, c: usize) -> Result<(), SudokuError> {
         let n = self.get(r, c)?;
         if n == 0 { return Ok(()); }
-        self.r_sets[r].insert(n);
-        self.c_sets[c].insert(n);
-        self.z_sets[r / 3][c / 3].insert(n);
-        self.grid[r][c] = 0;
+        self.unplace(r, c, self.zones[r][c] as usize, n);
         Ok(())
     }
 
     pub fn clear_all(&mut self) {
-        *self = Sudoku::new();
+        let extra_peers = std::mem::take(&mut self.extra_peers);
+        *self = SudokuN { zones: self.zones, extra_peers, ..Self::new() };
     }
 
     #[allow(clippy::needless_range_loop)]
-    pub fn set_grid(&mut self, grid: [[u8; 9]; 9]) -> Result<bool, &'static str> {
+    pub fn set_grid(&mut self, grid: [[u8; N]; N]) -> Result<(), SudokuError> {
+        error::validate_with(&grid, |(r1, c1), (r2, c2)| {
+            r1 == r2 || c1 == c2 || self.zones[r1][c1] == self.zones[r2][c2]
+            || self.extra_peers.of(r1, c1).contains(&(r2, c2))
+        })?;
         self.clear_all();
-        for i in 0..9 {
-            for j in 0..9 {
-                if !self.set(i, j, grid[i][j])? { return Ok(false); }
+        for i in 0..N {
+            for j in 0..N {
+                self.set(i, j, grid[i][j])?;
             }
         }
-        Ok(true)
+        Ok(())
     }
     
-    fn init_set() -> BitSet {
-        BitSet::full()
+    fn init_set() -> BitSetN<N> {
+        BitSetN::full()
     }
-    
-    fn is_valid(&self, r: usize, c: usize, n: u8) -> Result<bool, &'static str> {
-        Sudoku::restrain_index(r, c)?;
-        Ok(
-            self.r_sets[r].contains(n) &&
-            self.c_sets[c].contains(n) &&
-            self.z_sets[r / 3][c / 3].contains(n)
-        )
+
+    // The search works on indexes it has already checked, and looks up the
+    // zone once per cell rather than once per value tried. It's compiled
+    // separately for grids without extra peers, which skip that check.
+    fn fits<const EXTRA: bool>(&self, r: usize, c: usize, z: usize, n: u8) -> bool {
+        self.r_sets[r].contains(n) &&
+        self.c_sets[c].contains(n) &&
+        self.z_sets[z].contains(n) &&
+        (!EXTRA || self.extra_peers.of(r, c).iter().all(|&(pr, pc)| self.grid[pr][pc] != n))
+    }
+
+    fn place(&mut self, r: usize, c: usize, z: usize, n: u8) {
+        self.grid[r][c] = n;
+        self.r_sets[r].remove(n);
+        self.c_sets[c].remove(n);
+        self.z_sets[z].remove(n);
+    }
+
+    fn unplace(&mut self, r: usize, c: usize, z: usize, n: u8) {
+        self.grid[r][c] = 0;
+        self.r_sets[r].insert(n);
+        self.c_sets[c].insert(n);
+        self.z_sets[z].insert(n);
     }
 
     pub fn solve(&mut self) -> bool {
-        self.solve_at(0, 0)
+        if self.extra_peers.is_empty() { self.solve_at::<false>(0, 0) }
+        else { self.solve_at::<true>(0, 0) }
     }
 
-    fn solve_at(&mut self, r: usize, c: usize) -> bool {
-        if Sudoku::restrain_index(r, c).is_err() {
+    fn solve_at<const EXTRA: bool>(&mut self, r: usize, c: usize) -> bool {
+        if Self::restrain_index(r, c).is_err() {
             return true;
         }
 
-        let new_c = (c + 1) % 9;
-        let new_r = r + ((c + 1) / 9);
+        let new_c = (c + 1) % N;
+        let new_r = r + ((c + 1) / N);
 
         if self.get(r, c).unwrap() != 0 {
-            return self.solve_at(new_r, new_c);
+            return self.solve_at::<EXTRA>(new_r, new_c);
         }
 
-        for n in 1..=9 {
-            if !self.set(r, c, n).unwrap() { continue; }
-            if self.solve_at(new_r, new_c) { return true; }
-            self.clear(r, c).unwrap();
+        let z = self.zones[r][c] as usize;
+        for n in 1..=N as u8 {
+            if !self.fits::<EXTRA>(r, c, z, n) { continue; }
+            self.place(r, c, z, n);
+            if self.solve_at::<EXTRA>(new_r, new_c) { return true; }
+            self.unplace(r, c, z, n);
         }
 
         false
     }
 
-    fn restrain_index(r: usize, c: usize) -> Result<(), &'static str> {
-        if (0..9).contains(&r) && (0..9).contains(&c) { Ok(()) }
-        else { Err("Index greater than 9") }
+    pub fn count_solutions(&self, limit: usize) -> usize {
+        let mut sudoku = self.clone();
+        let mut count = 0;
+        if sudoku.extra_peers.is_empty() { sudoku.count_at::<false>(0, 0, limit, &mut count); }
+        else { sudoku.count_at::<true>(0, 0, limit, &mut count); }
+        count
+    }
+
+    pub fn is_unique(&self) -> bool {
+        self.count_solutions(2) == 1
+    }
+
+    fn count_at<const EXTRA: bool>(&mut self, r: usize, c: usize, limit: usize, count: &mut usize) {
+        if *count >= limit { return; }
+        if Self::restrain_index(r, c).is_err() {
+            *count += 1;
+            return;
+        }
+
+        let new_c = (c + 1) % N;
+        let new_r = r + ((c + 1) / N);
+
+        if self.get(r, c).unwrap() != 0 {
+            return self.count_at::<EXTRA>(new_r, new_c, limit, count);
+        }
+
+        let z = self.zones[r][c] as usize;
+        for n in 1..=N as u8 {
+            if !self.fits::<EXTRA>(r, c, z, n) { continue; }
+            self.place(r, c, z, n);
+            self.count_at::<EXTRA>(new_r, new_c, limit, count);
+            self.unplace(r, c, z, n);
+            if *count >= limit { return; }
+        }
+    }
+
+    fn restrain_index(r: usize, c: usize) -> Result<(), SudokuError> {
+        if (0..N).contains(&r) && (0..N).contains(&c) { Ok(()) }
+        else { Err(SudokuError::IndexOutOfRange { row: r, col: c, size: N }) }
+    }
+
+    pub fn grid(&self) -> &[[u8; N]; N] {
+        &self.grid
     }
 
     pub fn print_sets(&self) {
@@ -163,25 +241,110 @@ impl Sudoku {
             println!("{:?}", col);
         }
         println!("Zone vacant: ");
-        for row in &self.z_sets {
-            for zone in row {
-                println!("{:?}", zone);
-            }
+        for zone in &self.z_sets {
+            println!("{:?}", zone);
         }
     }
 }
 
-impl fmt::Display for Sudoku {
+impl Sudoku {
+    pub fn from_rules(grid: [[u8; 9]; 9], rules: &Rules) -> Result<Sudoku, SudokuError> {
+        let regions = rules.regions();
+        let mut new_sudoku = Sudoku {
+            zones: std::array::from_fn(|r| std::array::from_fn(|c| regions.region(r, c) as u8)),
+            extra_peers: rules.all_extra_peers().clone(),
+            ..Sudoku::new()
+        };
+        new_sudoku.set_grid(grid)?;
+        Ok(new_sudoku)
+    }
+}
+
+pub struct Bits;
+
+impl solver::Solver for Bits {
+    fn name(&self) -> &'static str {
+        "backtracking + bitsets"
+    }
+
+    fn solve(&self, grid: &[[u8; 9]; 9]) -> SolveOutcome {
+        let mut sudoku = match Sudoku::from(*grid) {
+            Ok(sudoku) => sudoku,
+            Err(err) => return SolveOutcome::Invalid(err),
+        };
+        if sudoku.solve() { SolveOutcome::Solved(*sudoku.grid()) }
+        else { SolveOutcome::Unsolvable }
+    }
+
+    fn solve_with(&self, grid: &[[u8; 9]; 9], rules: &Rules) -> SolveOutcome {
+        if !self.supports(rules) { return solver::UNSUPPORTED; }
+        let mut sudoku = match Sudoku::from_rules(*grid, rules) {
+            Ok(sudoku) => sudoku,
+            Err(err) => return SolveOutcome::Invalid(err),
+        };
+        if sudoku.solve() { SolveOutcome::Solved(*sudoku.grid()) }
+        else { SolveOutcome::Unsolvable }
+    }
+}
+
+impl<const N: usize, const BH: usize, const BW: usize> fmt::Display for SudokuN<N, BH, BW> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = N.to_string().len();
         let mut s = String::new();
-        (0..9).for_each(|r| {
-            (0..9).for_each(|c| {
-                s.push_str(&format!("{} ", self.get(r, c).unwrap()));
-                if c % 3 == 2 { s.push(' '); };
+        (0..N).for_each(|r| {
+            (0..N).for_each(|c| {
+                s.push_str(&format!("{:>width$} ", self.get(r, c).unwrap()));
+                if c % BW == BW - 1 { s.push(' '); };
             });
             s.push('\n');
-            if r % 3 == 2 { s.push('\n'); };
+            if r % BH == BH - 1 { s.push('\n'); };
         });
         write!(f, "{}", s)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count_stops_at_limit() {
+        let sudoku = Sudoku::new();
+        assert_eq!(sudoku.count_solutions(3), 3);
+        assert!(!sudoku.is_unique());
+    }
+
+    #[test]
+    fn rectangular_boxes() {
+        let mut sudoku = Sudoku6::new();
+        assert!(sudoku.solve());
+        assert!(error::validate_boxed(sudoku.grid(), 2, 3).is_ok());
+        assert!(sudoku.grid().iter().flatten().all(|&n| n != 0));
+
+        let mut grid = [[0u8; 12]; 12];
+        grid[0] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12];
+        let mut sudoku = Sudoku12::from(grid).unwrap();
+        assert!(sudoku.solve());
+        assert_eq!(sudoku.grid()[0], grid[0]);
+        assert!(error::validate_boxed(sudoku.grid(), 3, 4).is_ok());
+    }
+
+    #[test]
+    fn value_out_of_range_for_size() {
+        let mut grid = [[0u8; 4]; 4];
+        grid[1][1] = 5;
+        assert_eq!(
+            Sudoku4::from(grid).unwrap_err(),
+            SudokuError::ValueOutOfRange { row: 1, col: 1, value: 5, max: 4 }
+        );
+    }
+
+    #[test]
+    fn count_leaves_grid_untouched() {
+        let mut grid = [[0u8; 9]; 9];
+        grid[0] = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+        let sudoku = Sudoku::from(grid).unwrap();
+        sudoku.count_solutions(10);
+        assert_eq!(*sudoku.grid(), grid);
+    }
+}
@@ -0,0 +1,297 @@
+use std::fmt;
+use crate::solver::{self, SolveOutcome};
+use crate::error::{self, SudokuError};
+use crate::regions::Regions;
+use crate::rules::{Pair, Rules};
+
+// Knuth's Algorithm X on a toroidal doubly linked list. Sudoku is encoded as
+// exact cover with 729 rows, one per (row, col, value), and 324 columns for
+// the four constraint families: each cell filled, and each value once per
+// row, column and region. Extra houses add nine columns each. Cells that only
+// have to differ pairwise, like a knight's move apart, share secondary
+// columns, which may be covered at most once and aren't linked to the root.
+const COLUMNS: usize = 4 * 81;
+const ROOT: usize = 0;
+
+#[derive(Debug, Clone)]
+struct Links {
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    column: Vec<usize>,
+    // the candidate (r * 81 + c * 9 + n - 1) each node belongs to
+    row: Vec<usize>,
+    // number of nodes in each column, indexed by header
+    size: Vec<usize>,
+    // the first node of each candidate
+    first: Vec<usize>,
+}
+
+impl Links {
+    fn new(
+        regions: &Regions,
+        houses: &[[(usize, usize); 9]],
+        pairs: &[Pair],
+    ) -> Links {
+        let primary = COLUMNS + 9 * houses.len();
+        let headers = primary + 9 * pairs.len() + 1;
+        let mut links = Links {
+            left: (0..headers).map(|i| if i <= primary { (i + primary) % (primary + 1) } else { i }).collect(),
+            right: (0..headers).map(|i| if i <= primary { (i + 1) % (primary + 1) } else { i }).collect(),
+            up: (0..headers).collect(),
+            down: (0..headers).collect(),
+            column: (0..headers).collect(),
+            row: vec![usize::MAX; headers],
+            size: vec![0; headers],
+            first: Vec::with_capacity(729),
+        };
+        for r in 0..9 {
+            for c in 0..9 {
+                for n in 0..9 {
+                    let mut columns = vec![
+                        r * 9 + c,
+                        81 + r * 9 + n,
+                        2 * 81 + c * 9 + n,
+                        3 * 81 + regions.region(r, c) * 9 + n,
+                    ];
+                    for (h, house) in houses.iter().enumerate() {
+                        if house.contains(&(r, c)) { columns.push(COLUMNS + h * 9 + n); }
+                    }
+                    for (p, &(a, b)) in pairs.iter().enumerate() {
+                        if a == (r, c) || b == (r, c) { columns.push(primary + p * 9 + n); }
+                    }
+                    links.add_row(r * 81 + c * 9 + n, &columns);
+                }
+            }
+        }
+        links
+    }
+
+    fn add_row(&mut self, row: usize, columns: &[usize]) {
+        let first = self.column.len();
+        let end = first + columns.len() - 1;
+        self.first.push(first);
+        for (i, &col) in columns.iter().enumerate() {
+            let header = col + 1;
+            let node = first + i;
+            self.left.push(if node == first { end } else { node - 1 });
+            self.right.push(if node == end { first } else { node + 1 });
+            self.up.push(self.up[header]);
+            self.down.push(header);
+            self.column.push(header);
+            self.row.push(row);
+            let last = self.up[header];
+            self.down[last] = node;
+            self.up[header] = node;
+            self.size[header] += 1;
+        }
+    }
+
+    fn cover(&mut self, header: usize) {
+        let (left, right) = (self.left[header], self.right[header]);
+        self.right[left] = right;
+        self.left[right] = left;
+        let mut i = self.down[header];
+        while i != header {
+            let mut j = self.right[i];
+            while j != i {
+                let (up, down) = (self.up[j], self.down[j]);
+                self.down[up] = down;
+                self.up[down] = up;
+                self.size[self.column[j]] -= 1;
+                j = self.right[j];
+            }
+            i = self.down[i];
+        }
+    }
+
+    fn uncover(&mut self, header: usize) {
+        let mut i = self.up[header];
+        while i != header {
+            let mut j = self.left[i];
+            while j != i {
+                self.size[self.column[j]] += 1;
+                let (up, down) = (self.up[j], self.down[j]);
+                self.down[up] = j;
+                self.up[down] = j;
+                j = self.left[j];
+            }
+            i = self.up[i];
+        }
+        let (left, right) = (self.left[header], self.right[header]);
+        self.right[left] = header;
+        self.left[right] = header;
+    }
+
+    // Removes the columns satisfied by `row` as if it had been chosen.
+    // Returns false if one of them is already covered.
+    fn select(&mut self, row: usize) -> bool {
+        let node = self.first[row];
+        let mut j = node;
+        loop {
+            let header = self.column[j];
+            if self.left[self.right[header]] != header { return false; }
+            self.cover(header);
+            j = self.right[j];
+            if j == node { return true; }
+        }
+    }
+
+    // Calls `on_solution` with the chosen rows for every exact cover until
+    // it returns false. The links are fully restored on return.
+    fn search(&mut self, chosen: &mut Vec<usize>, on_solution: &mut dyn FnMut(&[usize]) -> bool) -> bool {
+        if self.right[ROOT] == ROOT { return on_solution(chosen); }
+
+        let mut header = self.right[ROOT];
+        let mut best = header;
+        while header != ROOT {
+            if self.size[header] < self.size[best] { best = header; }
+            header = self.right[header];
+        }
+        if self.size[best] == 0 { return true; }
+
+        self.cover(best);
+        let mut keep_going = true;
+        let mut i = self.down[best];
+        while i != best && keep_going {
+            chosen.push(self.row[i]);
+            let mut j = self.right[i];
+            while j != i { self.cover(self.column[j]); j = self.right[j]; }
+
+            keep_going = self.search(chosen, on_solution);
+
+            let mut j = self.left[i];
+            while j != i { self.uncover(self.column[j]); j = self.left[j]; }
+            chosen.pop();
+            i = self.down[i];
+        }
+        self.uncover(best);
+        keep_going
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Sudoku {
+    grid: [[u8; 9]; 9],
+    links: Links,
+}
+
+impl Sudoku {
+    pub fn from(grid: [[u8; 9]; 9]) -> Result<Sudoku, SudokuError> {
+        error::validate(&grid)?;
+        Ok(Sudoku::with_links(grid, Links::new(&Regions::standard(), &[], &[])))
+    }
+
+    pub fn from_rules(grid: [[u8; 9]; 9], rules: &Rules) -> Result<Sudoku, SudokuError> {
+        rules.validate(&grid)?;
+        Ok(Sudoku::with_links(grid, Links::new(rules.regions(), rules.houses(), &rules.pairwise_peers())))
+    }
+
+    fn with_links(grid: [[u8; 9]; 9], mut links: Links) -> Sudoku {
+        for (r, row) in grid.iter().enumerate() {
+            for (c, &n) in row.iter().enumerate() {
+                if n == 0 { continue; }
+                let selected = links.select(r * 81 + c * 9 + n as usize - 1);
+                debug_assert!(selected, "validated clues never share a column");
+            }
+        }
+        Sudoku { grid, links }
+    }
+
+    pub fn solve(&mut self) -> bool {
+        let mut solution = None;
+        self.links.search(&mut Vec::new(), &mut |rows| {
+            solution = Some(rows.to_vec());
+            false
+        });
+        match solution {
+            Some(rows) => {
+                for row in rows {
+                    self.grid[row / 81][row / 9 % 9] = (row % 9) as u8 + 1;
+                }
+                true
+            },
+            None => false,
+        }
+    }
+
+    pub fn count_solutions(&self, limit: usize) -> usize {
+        if limit == 0 { return 0; }
+        let mut links = self.links.clone();
+        let mut count = 0;
+        links.search(&mut Vec::new(), &mut |_| {
+            count += 1;
+            count < limit
+        });
+        count
+    }
+
+    pub fn is_unique(&self) -> bool {
+        self.count_solutions(2) == 1
+    }
+
+    pub fn grid(&self) -> &[[u8; 9]; 9] {
+        &self.grid
+    }
+}
+
+pub struct Dlx;
+
+impl solver::Solver for Dlx {
+    fn name(&self) -> &'static str {
+        "dancing links"
+    }
+
+    fn solve(&self, grid: &[[u8; 9]; 9]) -> SolveOutcome {
+        let mut sudoku = match Sudoku::from(*grid) {
+            Ok(sudoku) => sudoku,
+            Err(err) => return SolveOutcome::Invalid(err),
+        };
+        if sudoku.solve() { SolveOutcome::Solved(*sudoku.grid()) }
+        else { SolveOutcome::Unsolvable }
+    }
+
+    fn solve_with(&self, grid: &[[u8; 9]; 9], rules: &Rules) -> SolveOutcome {
+        if !self.supports(rules) { return solver::UNSUPPORTED; }
+        let mut sudoku = match Sudoku::from_rules(*grid, rules) {
+            Ok(sudoku) => sudoku,
+            Err(err) => return SolveOutcome::Invalid(err),
+        };
+        if sudoku.solve() { SolveOutcome::Solved(*sudoku.grid()) }
+        else { SolveOutcome::Unsolvable }
+    }
+}
+
+impl fmt::Display for Sudoku {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut s = String::new();
+        (0..9).for_each(|r| {
+            (0..9).for_each(|c| {
+                s.push_str(&format!("{} ", self.grid[r][c]));
+                if c % 3 == 2 { s.push(' '); };
+            });
+            s.push('\n');
+            if r % 3 == 2 { s.push('\n'); };
+        });
+        write!(f, "{}", s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{EASY, SOLUTION};
+
+    #[test]
+    fn counts_solutions() {
+        let sudoku = Sudoku::from([[0u8; 9]; 9]).unwrap();
+        assert_eq!(sudoku.count_solutions(5), 5);
+        assert!(!sudoku.is_unique());
+
+        let mut sudoku = Sudoku::from(EASY).unwrap();
+        assert!(sudoku.is_unique());
+        assert!(sudoku.solve());
+        assert_eq!(*sudoku.grid(), SOLUTION);
+    }
+}
@@ -0,0 +1,315 @@
+use std::fmt;
+use crate::error::SudokuError;
+use crate::rules::{self, Constraint, Pair};
+use crate::wavefunc::{BitSet, Sudoku};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DotKind {
+    // Kropki white dot: the digits are consecutive
+    White,
+    // Kropki black dot: one digit is twice the other
+    Black,
+    // the digits add up to 10
+    X,
+    // the digits add up to 5
+    V,
+}
+
+impl DotKind {
+    pub fn name(&self) -> &'static str {
+        use DotKind::*;
+        match self {
+            White => "white",
+            Black => "black",
+            X => "x",
+            V => "v",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<DotKind> {
+        use DotKind::*;
+        [White, Black, X, V].into_iter()
+            .find(|kind| kind.name() == name)
+    }
+
+    pub fn holds(&self, a: u8, b: u8) -> bool {
+        use DotKind::*;
+        match self {
+            White => a.abs_diff(b) == 1,
+            Black => a == 2 * b || b == 2 * a,
+            X => a + b == 10,
+            V => a + b == 5,
+        }
+    }
+}
+
+impl fmt::Display for DotKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+// A marker on the edge between two orthogonally adjacent cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dot {
+    kind: DotKind,
+    cells: Pair,
+}
+
+impl Dot {
+    pub fn new(kind: DotKind, a: (usize, usize), b: (usize, usize)) -> Result<Dot, SudokuError> {
+        if let Some(&(row, col)) = [a, b].iter().find(|&&(r, c)| r >= 9 || c >= 9) {
+            return Err(SudokuError::IndexOutOfRange { row, col, size: 9 });
+        }
+        if a.0.abs_diff(b.0) + a.1.abs_diff(b.1) != 1 {
+            return Err(SudokuError::InvalidConstraint { reason: "a dot must sit between orthogonally adjacent cells" });
+        }
+        Ok(Dot { kind, cells: (a.min(b), a.max(b)) })
+    }
+
+    pub fn kind(&self) -> DotKind {
+        self.kind
+    }
+
+    pub fn cells(&self) -> Pair {
+        self.cells
+    }
+}
+
+impl fmt::Display for Dot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ((r1, c1), (r2, c2)) = self.cells;
+        write!(f, "{} r{}c{} r{}c{}", self.kind, r1 + 1, c1 + 1, r2 + 1, c2 + 1)
+    }
+}
+
+// Every dot of a puzzle, and the kinds of dot that are all given: where the
+// edge between two adjacent cells has no dot at all, their digits don't
+// match any of those kinds, as in consecutive sudoku or XV with all the
+// markers shown.
+#[derive(Debug, Clone)]
+pub struct Dots {
+    dots: Vec<Dot>,
+    negative: Vec<DotKind>,
+    // the pairs that carry a rule, each with the digits it lets the second
+    // cell take for every digit of the first, indexed by that digit
+    edges: Vec<(Pair, [BitSet; 10])>,
+}
+
+impl Dots {
+    pub fn new(dots: Vec<Dot>, negative: &[DotKind]) -> Dots {
+        let mut pairs: Vec<Pair> = dots.iter().map(|dot| dot.cells).collect();
+        if !negative.is_empty() {
+            for r in 0..9 {
+                for c in 0..9 {
+                    if c < 8 { pairs.push(((r, c), (r, c + 1))); }
+                    if r < 8 { pairs.push(((r, c), (r + 1, c))); }
+                }
+            }
+        }
+        pairs.sort();
+        pairs.dedup();
+
+        let edges = pairs.into_iter()
+            .map(|pair| {
+                let kinds: Vec<DotKind> = dots.iter()
+                    .filter(|dot| dot.cells == pair)
+                    .map(|dot| dot.kind)
+                    .collect();
+                let partners = std::array::from_fn(|a| (1..=9u8)
+                    .filter(|&b| if kinds.is_empty() { !negative.iter().any(|kind| kind.holds(a as u8, b)) }
+                        else { kinds.iter().all(|kind| kind.holds(a as u8, b)) })
+                    .fold(BitSet::new(), |set, b| set.insert(b)));
+                (pair, partners)
+            })
+            .collect();
+        Dots { dots, negative: negative.to_vec(), edges }
+    }
+
+    // Reads one dot per row of text, its kind and the two cells either side
+    // of it as in `white r1c1 r1c2`, and a row such as `negative white black`
+    // for the kinds that are all given.
+    pub fn parse(text: &str) -> Result<Dots, SudokuError> {
+        let mut dots = Vec::new();
+        let mut negative = Vec::new();
+        for (_, row) in rules::clue_rows(text) {
+            let words: Vec<&str> = row.split_whitespace().collect();
+            let kind = |word: &str| DotKind::from_name(word)
+                .ok_or(SudokuError::InvalidConstraint { reason: "dots are white, black, x or v" });
+            match words[..] {
+                ["negative"] => return Err(SudokuError::InvalidConstraint { reason: "a negative constraint must name the kinds of dot it covers" }),
+                ["negative", ref kinds @ ..] => {
+                    for &word in kinds {
+                        negative.push(kind(word)?);
+                    }
+                },
+                [name, a, b] => {
+                    let (a, b) = rules::parse_cell(a).zip(rules::parse_cell(b))
+                        .ok_or(SudokuError::InvalidConstraint { reason: "cells must be written like r1c1" })?;
+                    dots.push(Dot::new(kind(name)?, a, b)?);
+                },
+                _ => return Err(SudokuError::InvalidConstraint { reason: "a dot needs its kind and two cells" }),
+            }
+        }
+        Ok(Dots::new(dots, &negative))
+    }
+
+    pub fn dots(&self) -> &[Dot] {
+        &self.dots
+    }
+
+    pub fn negative(&self) -> &[DotKind] {
+        &self.negative
+    }
+}
+
+impl fmt::Display for Dots {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for dot in &self.dots {
+            writeln!(f, "{dot}")?;
+        }
+        if !self.negative.is_empty() {
+            let kinds: Vec<&str> = self.negative.iter().map(DotKind::name).collect();
+            writeln!(f, "negative {}", kinds.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+impl Constraint for Dots {
+    fn is_valid(&self, sudoku: &Sudoku) -> bool {
+        let grid = sudoku.grid();
+        self.edges.iter().all(|&(((r1, c1), (r2, c2)), partners)| {
+            let (a, b) = (grid[r1][c1], grid[r2][c2]);
+            a == 0 || b == 0 || partners[a as usize].contains(b)
+        })
+    }
+
+    // Keeps the digits of each cell that still have a partner next door.
+    fn prune(&self, sudoku: &Sudoku) -> Sudoku {
+        let mut sudoku = *sudoku;
+        for &((first, second), partners) in &self.edges {
+            let a = sudoku.cell(first.0, first.1).possible_values();
+            let b = sudoku.cell(second.0, second.1).possible_values();
+            let allowed = [
+                a.iter().filter(|&n| !partners[n as usize].intersection(b).is_empty())
+                    .fold(BitSet::new(), |set, n| set.insert(n)),
+                a.iter().fold(BitSet::new(), |set, n| set.union(partners[n as usize].intersection(b))),
+            ];
+            sudoku = rules::restrict(&sudoku, &[first, second], &allowed);
+        }
+        sudoku
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::Rules;
+    use crate::test_util::{self, SOLUTION};
+
+    #[test]
+    fn parses_dots() {
+        let dots = Dots::parse("
+            white r1c2 r1c1   # either way round
+            X r5c5 r6c5
+            negative white x
+        ").unwrap();
+        assert_eq!(dots.dots()[0].cells(), ((0, 0), (0, 1)));
+        assert_eq!(dots.dots()[1].kind(), DotKind::X);
+        assert_eq!(dots.negative(), [DotKind::White, DotKind::X]);
+        assert_eq!(dots.to_string(), "white r1c1 r1c2\nx r5c5 r6c5\nnegative white x\n");
+
+        assert!(Dots::parse("white r1c1 r2c2").is_err());
+        assert!(Dots::parse("grey r1c1 r1c2").is_err());
+        assert!(Dots::parse("negative red").is_err());
+        assert_eq!(
+            Dots::parse("white r1c1 r1c2\nnegative  # every kind?").map(|_| ()),
+            Err(SudokuError::InvalidConstraint { reason: "a negative constraint must name the kinds of dot it covers" })
+        );
+    }
+
+    #[test]
+    fn kropki_dots_pair_their_digits() {
+        let mut grid = [[0u8; 9]; 9];
+        grid[0][0] = 5;
+        let sudoku = test_util::pruned([Dots::parse("white r1c1 r1c2").unwrap()], grid);
+        assert_eq!(sudoku.cell(0, 1).possible_values(), BitSet::new().insert(4).insert(6));
+
+        // 5, 7 and 9 have neither a half nor a double
+        let sudoku = test_util::pruned([Dots::parse("black r1c1 r1c2").unwrap()], [[0u8; 9]; 9]);
+        for n in [5, 7, 9] {
+            assert!(!sudoku.cell(0, 0).possible_values().contains(n));
+        }
+        assert_eq!(sudoku.cell(0, 1).possible_values().size(), 6);
+
+        // both dots on one edge leave only 1 and 2
+        let sudoku = test_util::pruned([Dots::parse("white r5c5 r6c5\nblack r5c5 r6c5").unwrap()], [[0u8; 9]; 9]);
+        assert_eq!(sudoku.cell(4, 4).possible_values(), BitSet::new().insert(1).insert(2));
+        assert_eq!(sudoku.cell(5, 4).possible_values(), BitSet::new().insert(1).insert(2));
+    }
+
+    #[test]
+    fn xv_dots_add_up() {
+        let mut grid = [[0u8; 9]; 9];
+        grid[4][4] = 3;
+        let sudoku = test_util::pruned([Dots::parse("x r5c5 r5c6").unwrap()], grid);
+        assert_eq!(sudoku.cell(4, 5).possible_values(), BitSet::from_single(7));
+
+        let sudoku = test_util::pruned([Dots::parse("v r5c5 r5c6").unwrap()], [[0u8; 9]; 9]);
+        assert_eq!(sudoku.cell(4, 4).possible_values(), (1..=4).fold(BitSet::new(), |set, n| set.insert(n)));
+
+        let dots = Dots::parse("x r1c1 r1c2").unwrap();
+        let mut grid = [[0u8; 9]; 9];
+        grid[0][..2].copy_from_slice(&[4, 6]);
+        assert!(dots.is_valid(&Sudoku::from(grid).unwrap()));
+        grid[0][1] = 5;
+        assert!(!dots.is_valid(&Sudoku::from(grid).unwrap()));
+    }
+
+    #[test]
+    fn negative_constraint_covers_edges_without_a_dot() {
+        let mut grid = [[0u8; 9]; 9];
+        grid[0][0] = 5;
+        // without a dot, the neighbours of 5 can't be 4 or 6
+        let sudoku = test_util::pruned([Dots::parse("negative white").unwrap()], grid);
+        assert!(!sudoku.cell(1, 0).possible_values().contains(4));
+        assert!(!sudoku.cell(0, 1).possible_values().contains(6));
+        assert!(sudoku.cell(1, 1).possible_values().contains(6));
+
+        // a dot on the edge overrides it
+        let sudoku = test_util::pruned([Dots::parse("white r1c1 r1c2\nnegative white").unwrap()], grid);
+        assert_eq!(sudoku.cell(0, 1).possible_values(), BitSet::new().insert(4).insert(6));
+        assert!(!sudoku.cell(1, 0).possible_values().contains(4));
+
+        grid[0][0] = 3;
+        let sudoku = test_util::pruned([Dots::parse("negative x v").unwrap()], grid);
+        assert!(!sudoku.cell(0, 1).possible_values().contains(7));
+        assert!(!sudoku.cell(0, 1).possible_values().contains(2));
+        assert!(sudoku.cell(0, 1).possible_values().contains(4));
+
+        // kinds left out of the list still go unmarked
+        let sudoku = test_util::pruned([Dots::parse("negative x").unwrap()], grid);
+        assert!(sudoku.cell(0, 1).possible_values().contains(2));
+    }
+
+    #[test]
+    #[allow(clippy::needless_range_loop)]
+    fn solves_kropki_and_xv() {
+        let mut dots = Vec::new();
+        for r in 0..9 {
+            for c in 0..9 {
+                for (r2, c2) in [(r, c + 1), (r + 1, c)].into_iter().filter(|&(r2, c2)| r2 < 9 && c2 < 9) {
+                    let (a, b) = (SOLUTION[r][c], SOLUTION[r2][c2]);
+                    let kind = [DotKind::White, DotKind::Black, DotKind::X, DotKind::V].into_iter()
+                        .find(|kind| kind.holds(a, b));
+                    if let Some(kind) = kind { dots.push(Dot::new(kind, (r, c), (r2, c2)).unwrap()); }
+                }
+            }
+        }
+        let mut rules = Rules::standard();
+        rules.add_dots(Dots::new(dots, &[DotKind::White, DotKind::Black, DotKind::X, DotKind::V]));
+        let sudoku = Sudoku::from_rules([[0u8; 9]; 9], &rules).unwrap();
+        assert_eq!(sudoku.solved_with(&rules).unwrap().grid(), SOLUTION);
+    }
+}
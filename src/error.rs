@@ -0,0 +1,101 @@
+use std::{error, fmt};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SudokuError {
+    IndexOutOfRange { row: usize, col: usize, size: usize },
+    ValueOutOfRange { row: usize, col: usize, value: u8, max: u8 },
+    Conflict { row: usize, col: usize, value: u8, with: (usize, usize) },
+    InvalidConstraint { reason: &'static str },
+}
+
+impl fmt::Display for SudokuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use SudokuError::*;
+        match self {
+            IndexOutOfRange { row, col, size } =>
+                write!(f, "cell ({row}, {col}) is outside the {size}x{size} grid"),
+            ValueOutOfRange { row, col, value, max } =>
+                write!(f, "value {value} at ({row}, {col}) is not in range 1..={max}"),
+            Conflict { row, col, value, with: (other_row, other_col) } =>
+                write!(f, "value {value} at ({row}, {col}) conflicts with the same value at ({other_row}, {other_col})"),
+            InvalidConstraint { reason } =>
+                write!(f, "invalid constraint: {reason}"),
+        }
+    }
+}
+
+impl error::Error for SudokuError {}
+
+// Checks that every clue is in 0..=9 and that no two equal clues share a
+// row, column or box. Conflicts are reported on the later of the two cells.
+pub fn validate(grid: &[[u8; 9]; 9]) -> Result<(), SudokuError> {
+    validate_boxed(grid, 3, 3)
+}
+
+// `validate` for an N x N grid with boxes of `box_height` by `box_width`.
+pub fn validate_boxed<const N: usize>(
+    grid: &[[u8; N]; N],
+    box_height: usize,
+    box_width: usize,
+) -> Result<(), SudokuError> {
+    validate_with(grid, |(r, c), (row, col)| {
+        r == row || c == col
+        || (r / box_height == row / box_height && c / box_width == col / box_width)
+    })
+}
+
+// `validate` for any layout, where `sees(a, b)` tells whether cells a and b
+// may not hold the same value.
+pub fn validate_with<const N: usize>(
+    grid: &[[u8; N]; N],
+    sees: impl Fn((usize, usize), (usize, usize)) -> bool,
+) -> Result<(), SudokuError> {
+    for (row, cells) in grid.iter().enumerate() {
+        for (col, &value) in cells.iter().enumerate() {
+            if value as usize > N { return Err(SudokuError::ValueOutOfRange { row, col, value, max: N as u8 }); }
+        }
+    }
+    for row in 0..N {
+        for col in 0..N {
+            let value = grid[row][col];
+            if value == 0 { continue; }
+            let earlier_peer = (0..N).flat_map(|r| (0..N).map(move |c| (r, c)))
+                .take_while(|&cell| cell != (row, col))
+                .find(|&(r, c)| grid[r][c] == value && sees((r, c), (row, col)));
+            if let Some(with) = earlier_peer {
+                return Err(SudokuError::Conflict { row, col, value, with });
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_conflicting_cell() {
+        let mut grid = [[0u8; 9]; 9];
+        grid[1][1] = 4;
+        grid[2][2] = 4;
+        assert_eq!(
+            validate(&grid),
+            Err(SudokuError::Conflict { row: 2, col: 2, value: 4, with: (1, 1) })
+        );
+    }
+
+    #[test]
+    fn reports_value_out_of_range() {
+        let mut grid = [[0u8; 9]; 9];
+        grid[3][5] = 12;
+        let err = validate(&grid).unwrap_err();
+        assert_eq!(err, SudokuError::ValueOutOfRange { row: 3, col: 5, value: 12, max: 9 });
+        assert_eq!(err.to_string(), "value 12 at (3, 5) is not in range 1..=9");
+
+        let mut grid = [[0u8; 16]; 16];
+        grid[3][5] = 17;
+        let err = validate_boxed(&grid, 4, 4).unwrap_err();
+        assert_eq!(err.to_string(), "value 17 at (3, 5) is not in range 1..=16");
+    }
+}
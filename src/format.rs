@@ -0,0 +1,523 @@
+use crate::parse::{self, ParseError};
+use crate::wavefunc::{BitSet, Cell, Sudoku};
+
+// Reads a puzzle written on one line: the 81 cells row by row, digits for
+// clues and `.` or `0` for blanks. Anything after the cells, set off by
+// whitespace or punctuation, is a comment and ignored.
+pub fn from_line(line: &str) -> Result<[[u8; 9]; 9], ParseError> {
+    from_numbered_line(line, 1)
+}
+
+pub(crate) fn from_numbered_line(line: &str, number: usize) -> Result<[[u8; 9]; 9], ParseError> {
+    let error = |column, reason| ParseError { line: number, column, reason };
+    let mut grid = [[0u8; 9]; 9];
+    let mut chars = line.trim_end_matches(['\r', '\n']).chars();
+    for i in 0..81 {
+        grid[i / 9][i % 9] = match chars.next() {
+            Some('.' | '0') => 0,
+            Some(ch @ '1'..='9') => ch as u8 - b'0',
+            Some(_) => return Err(error(i + 1, "expected a digit or '.'")),
+            None => return Err(error(i + 1, "a puzzle needs 81 cells")),
+        };
+    }
+    match chars.next() {
+        Some(ch) if ch.is_ascii_digit() || ch == '.' => Err(error(82, "a puzzle has only 81 cells")),
+        Some(ch) if ch.is_alphanumeric() => Err(error(82, "a comment must be set off from the cells")),
+        _ => Ok(grid),
+    }
+}
+
+// Writes a puzzle as one line of 81 cells, with `.` for blanks.
+pub fn to_line(grid: &[[u8; 9]; 9]) -> String {
+    grid.iter()
+        .flatten()
+        .map(|&n| cell(n))
+        .collect()
+}
+
+fn cell(n: u8) -> char {
+    if n == 0 { '.' } else { (b'0' + n) as char }
+}
+
+// Reads a collection such as `to_lines` writes, the way `parse::grids`
+// does, but gives up at the first puzzle that can't be read.
+pub fn from_lines(text: &str) -> Result<Vec<[[u8; 9]; 9]>, ParseError> {
+    parse::grids(text).into_iter().collect()
+}
+
+pub fn to_lines(grids: &[[[u8; 9]; 9]]) -> String {
+    grids.iter()
+        .map(|grid| to_line(grid) + "\n")
+        .collect()
+}
+
+// Reads a SadMan Software `.sdk` file: `#` lines of metadata, such as `#A`
+// for the author, and nine rows of nine cells. A `[Puzzle]` header may come
+// first, and any later section, such as the state of a saved game, is
+// skipped.
+pub fn from_sdk(text: &str) -> Result<[[u8; 9]; 9], ParseError> {
+    let mut block = Vec::new();
+    let mut end = text.lines().count() + 1;
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim_end();
+        if line.eq_ignore_ascii_case("[puzzle]") && block.is_empty() { continue; }
+        if line.starts_with('[') {
+            end = i + 1;
+            break;
+        }
+        if line.is_empty() || line.starts_with('#') { continue; }
+        block.push((i + 1, line));
+    }
+    parse::rows(&block, end)
+}
+
+pub fn to_sdk(grid: &[[u8; 9]; 9]) -> String {
+    grid.iter()
+        .map(|row| row.iter().map(|&n| cell(n)).collect::<String>() + "\n")
+        .collect()
+}
+
+// Reads a Simple Sudoku `.ss` file: nine rows of cells with `|` between the
+// boxes and dashes between the bands, in a frame or not.
+pub fn from_ss(text: &str) -> Result<[[u8; 9]; 9], ParseError> {
+    let block: Vec<(usize, &str)> = text.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim_end()))
+        .filter(|(_, line)| !line.is_empty() && !is_border(line))
+        .collect();
+    parse::rows(&block, text.lines().count() + 1)
+}
+
+pub fn to_ss(grid: &[[u8; 9]; 9]) -> String {
+    let mut text = String::new();
+    for (r, row) in grid.iter().enumerate() {
+        if r == 3 || r == 6 { text.push_str("-----------\n"); }
+        for (c, &n) in row.iter().enumerate() {
+            if c == 3 || c == 6 { text.push('|'); }
+            text.push(cell(n));
+        }
+        text.push('\n');
+    }
+    text
+}
+
+// A frame or a line between bands: dashes, with corners and crossings.
+fn is_border(line: &str) -> bool {
+    line.contains('-') && line.chars().all(|ch| ch.is_whitespace() || "-+*|.:'".contains(ch))
+}
+
+// Reads a grid of candidates as Simple Sudoku and HoDoKu copy them, every
+// cell the digits it can still take:
+//
+//     .----------------.----------------.----------------.
+//     | 5    3    12   | 26   7    1268 | 1489 149  248  |
+//     ...
+//     :----------------+----------------+----------------:
+//
+// A cell with a single digit is taken as solved, and a lone `0` is a cell
+// left without candidates.
+pub fn from_pencil_marks(text: &str) -> Result<Sudoku, ParseError> {
+    let mut grid = [[Cell::Unknown(BitSet::full()); 9]; 9];
+    let mut row = 0;
+    for (i, line) in text.lines().enumerate() {
+        let error = |column, reason| Err(ParseError { line: i + 1, column, reason });
+        let line = line.trim_end();
+        if line.is_empty() || is_border(line) { continue; }
+        if row == 9 { return error(1, "a grid has only 9 rows"); }
+        let mut col = 0;
+        // where the cell being read starts, and its digits so far
+        let mut digits: Option<(usize, BitSet)> = None;
+        for (x, ch) in line.chars().chain(std::iter::once(' ')).enumerate() {
+            if let Some(n) = ch.to_digit(10).map(|n| n as u8) {
+                let (start, set) = digits.unwrap_or((x, BitSet::new()));
+                if digits.is_some() && (n == 0 || set.is_empty()) {
+                    return error(x + 1, "a cell without candidates is a lone 0");
+                }
+                if n == 0 {
+                    digits = Some((start, set));
+                    continue;
+                }
+                if set.contains(n) { return error(x + 1, "a cell has each candidate once"); }
+                digits = Some((start, set.insert(n)));
+                continue;
+            }
+            if !(ch.is_whitespace() || ch == '|') { return error(x + 1, "expected a candidate or a separator"); }
+            let Some((start, set)) = digits.take() else { continue; };
+            if col == 9 { return error(start + 1, "a row has only 9 cells"); }
+            grid[row][col] = match set.iter().next() {
+                None => Cell::Invalid(None),
+                Some(n) if set.size() == 1 => Cell::Known(n),
+                _ => Cell::Unknown(set),
+            };
+            col += 1;
+        }
+        if col < 9 { return error(line.chars().count() + 1, "a row needs 9 cells"); }
+        row += 1;
+    }
+    if row < 9 {
+        return Err(ParseError { line: text.lines().count() + 1, column: 1, reason: "a grid needs 9 rows" });
+    }
+    Ok(Sudoku::from_cells(grid))
+}
+
+// Writes the candidates of every cell the way Simple Sudoku copies them.
+pub fn to_ss_pencil_marks(sudoku: &Sudoku) -> String {
+    pencil_marks(sudoku, [['*', '-', '*'], ['|', '+', '|'], ['*', '-', '*']])
+}
+
+// Writes the candidates of every cell the way HoDoKu copies them.
+pub fn to_hodoku_pencil_marks(sudoku: &Sudoku) -> String {
+    pencil_marks(sudoku, [['.', '.', '.'], [':', '+', ':'], ['\'', '\'', '\'']])
+}
+
+fn pencil_marks(sudoku: &Sudoku, corners: [[char; 3]; 3]) -> String {
+    let marks: Vec<Vec<String>> = (0..9)
+        .map(|r| (0..9)
+            .map(|c| match sudoku.cell(r, c).possible_values() {
+                set if set.is_empty() => "0".to_string(),
+                set => set.iter().map(cell).collect(),
+            })
+            .collect())
+        .collect();
+    framed(&marks, corners)
+}
+
+// Lines up the text of every cell in a frame. `corners` are the left, inner
+// and right corners of its top, of the lines between bands and of its bottom.
+fn framed(marks: &[Vec<String>], corners: [[char; 3]; 3]) -> String {
+    // every column as wide as its widest cell
+    let widths: Vec<usize> = (0..9)
+        .map(|c| marks.iter().map(|row| row[c].len()).max().unwrap_or(0))
+        .collect();
+    let border = |[left, inner, right]: [char; 3]| {
+        let mut line = String::new();
+        for b in 0..3 {
+            line.push(if b == 0 { left } else { inner });
+            line.push_str(&"-".repeat(widths[3 * b..3 * b + 3].iter().sum::<usize>() + 6));
+        }
+        line.push(right);
+        line + "\n"
+    };
+
+    let mut text = border(corners[0]);
+    for (r, row) in marks.iter().enumerate() {
+        if r == 3 || r == 6 { text.push_str(&border(corners[1])); }
+        for b in 0..3 {
+            let cells: Vec<String> = (3 * b..3 * b + 3)
+                .map(|c| format!("{:<width$}", row[c], width = widths[c]))
+                .collect();
+            text.push_str(&format!("| {} ", cells.join("  ")));
+        }
+        text.push_str("|\n");
+    }
+    text + &border(corners[2])
+}
+
+// Writes the state of every cell so that `from_candidates` gives it back
+// exactly: solved cells as their digit, the others as their candidates in
+// braces, so `{5}` is a cell left with one candidate, `{}` a cell left with
+// none and `!5` a digit placed where it didn't fit.
+pub fn to_candidates(sudoku: &Sudoku) -> String {
+    let marks: Vec<Vec<String>> = (0..9)
+        .map(|r| (0..9)
+            .map(|c| match sudoku.cell(r, c) {
+                Cell::Known(n) => cell(n).to_string(),
+                Cell::Unknown(set) => format!("{{{}}}", set.iter().map(cell).collect::<String>()),
+                Cell::Invalid(Some(n)) => format!("!{n}"),
+                Cell::Invalid(None) => "{}".to_string(),
+            })
+            .collect())
+        .collect();
+    framed(&marks, [['+'; 3]; 3])
+}
+
+pub fn from_candidates(text: &str) -> Result<Sudoku, ParseError> {
+    let mut grid = [[Cell::Invalid(None); 9]; 9];
+    let mut row = 0;
+    for (i, line) in text.lines().enumerate() {
+        let error = |column, reason| Err(ParseError { line: i + 1, column, reason });
+        let line = line.trim_end();
+        if line.is_empty() || is_border(line) { continue; }
+        if row == 9 { return error(1, "a grid has only 9 rows"); }
+        let chars: Vec<char> = line.chars().collect();
+        let mut col = 0;
+        let mut x = 0;
+        while x < chars.len() {
+            let start = x;
+            let value = match chars[x] {
+                ch if ch.is_whitespace() || ch == '|' => {
+                    x += 1;
+                    continue;
+                },
+                '{' => {
+                    let mut set = BitSet::new();
+                    loop {
+                        x += 1;
+                        match chars.get(x) {
+                            Some('}') => break,
+                            Some(&ch @ '1'..='9') if set.contains(ch as u8 - b'0') => {
+                                return error(x + 1, "a cell has each candidate once");
+                            },
+                            Some(&ch @ '1'..='9') => set = set.insert(ch as u8 - b'0'),
+                            _ => return error(x + 1, "expected a candidate or '}'"),
+                        }
+                    }
+                    if set.is_empty() { Cell::Invalid(None) } else { Cell::Unknown(set) }
+                },
+                '!' => {
+                    x += 1;
+                    match chars.get(x) {
+                        Some(&ch @ '1'..='9') => Cell::Invalid(Some(ch as u8 - b'0')),
+                        _ => return error(x + 1, "expected the digit that didn't fit"),
+                    }
+                },
+                ch @ '1'..='9' => Cell::Known(ch as u8 - b'0'),
+                _ => return error(x + 1, "expected a digit, '{', '!' or a separator"),
+            };
+            x += 1;
+            if col == 9 { return error(start + 1, "a row has only 9 cells"); }
+            grid[row][col] = value;
+            col += 1;
+        }
+        if col < 9 { return error(chars.len() + 1, "a row needs 9 cells"); }
+        row += 1;
+    }
+    if row < 9 {
+        return Err(ParseError { line: text.lines().count() + 1, column: 1, reason: "a grid needs 9 rows" });
+    }
+    Ok(Sudoku::from_cells(grid))
+}
+
+// Reads a puzzle in HoDoKu's library format,
+//
+//     :0000:x:53..7....+6..195...:213 413::
+//
+// whose third field has the cells, digits for givens and `+` before a digit
+// placed since, and whose fourth has the candidates deleted beyond those the
+// digits rule out, each as the candidate, its row and its column. Returns
+// the givens and the state of the grid.
+pub fn from_hodoku(line: &str) -> Result<([[u8; 9]; 9], Sudoku), ParseError> {
+    let error = |column, reason| Err(ParseError { line: 1, column, reason });
+    let fields: Vec<&str> = line.trim_end_matches(['\r', '\n']).split(':').collect();
+    if fields.len() < 5 || !fields[0].is_empty() {
+        return error(1, "expected `:type:technique:cells:deleted candidates:`");
+    }
+    let start = fields[..3].iter().map(|field| field.chars().count() + 1).sum::<usize>() + 1;
+
+    let mut givens = [[0u8; 9]; 9];
+    let mut values = [[0u8; 9]; 9];
+    let mut column = start;
+    let mut i = 0;
+    let mut placed = false;
+    for ch in fields[3].chars() {
+        if i == 81 { return error(column, "a puzzle has only 81 cells"); }
+        match ch {
+            '+' if !placed => {
+                placed = true;
+                column += 1;
+                continue;
+            },
+            '.' | '0' if !placed => {},
+            '1'..='9' => {
+                values[i / 9][i % 9] = ch as u8 - b'0';
+                if !placed { givens[i / 9][i % 9] = ch as u8 - b'0'; }
+            },
+            _ => return error(column, "expected a digit, '.' or '+'"),
+        }
+        placed = false;
+        i += 1;
+        column += 1;
+    }
+    if placed || i < 81 { return error(column, "a puzzle needs 81 cells"); }
+    if crate::error::validate(&values).is_err() { return error(start, "the digits break the rules"); }
+
+    let mut sudoku = with_values(&values);
+    column += 1;
+    for word in fields[4].split(' ') {
+        let digits: Vec<u8> = word.bytes().map(|b| b.wrapping_sub(b'0')).collect();
+        match digits[..] {
+            [] => {},
+            [n, r, c] if digits.iter().all(|d| (1..=9).contains(d)) => {
+                sudoku = sudoku.without(r as usize - 1, c as usize - 1, n);
+            },
+            _ => return error(column, "a deleted candidate is written as its digit, row and column"),
+        }
+        column += word.chars().count() + 1;
+    }
+    Ok((givens, sudoku))
+}
+
+// Writes `state` in HoDoKu's library format, marking the digits that aren't
+// among `givens` as placed.
+pub fn to_hodoku(givens: &[[u8; 9]; 9], state: &Sudoku) -> String {
+    let values = state.grid();
+    let placed = with_values(&values);
+    let mut cells = String::new();
+    let mut deleted = Vec::new();
+    for (r, row) in values.iter().enumerate() {
+        for (c, &n) in row.iter().enumerate() {
+            if n != 0 && givens[r][c] != n { cells.push('+'); }
+            cells.push(cell(n));
+            let left = state.cell(r, c).possible_values();
+            deleted.extend(placed.cell(r, c).possible_values().iter()
+                .filter(|&d| !left.contains(d))
+                .map(|d| format!("{d}{}{}", r + 1, c + 1)));
+        }
+    }
+    format!(":0000:x:{cells}:{}::", deleted.join(" "))
+}
+
+// The candidates left once `values` are placed, without checking them.
+fn with_values(values: &[[u8; 9]; 9]) -> Sudoku {
+    let mut sudoku = Sudoku::empty();
+    for (r, row) in values.iter().enumerate() {
+        for (c, &n) in row.iter().enumerate() {
+            if n != 0 { sudoku = sudoku.set(r, c, n); }
+        }
+    }
+    sudoku
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{EASY, EASY_LINE};
+
+    #[test]
+    fn reads_and_writes_one_line() {
+        let grid = from_line(EASY_LINE).unwrap();
+        assert_eq!(grid, EASY);
+        assert_eq!(to_line(&grid), EASY_LINE);
+
+        assert_eq!(from_line(&EASY_LINE.replace('.', "0")), Ok(grid));
+        assert_eq!(from_line(&format!("{EASY_LINE} # easy")), Ok(grid));
+        assert_eq!(from_line(&format!("{EASY_LINE};1.2\r\n")), Ok(grid));
+    }
+
+    #[test]
+    fn reports_where_a_line_goes_wrong() {
+        assert_eq!(
+            from_line(&EASY_LINE.replacen('.', "x", 1)),
+            Err(ParseError { line: 1, column: 3, reason: "expected a digit or '.'" })
+        );
+        assert_eq!(from_line(&EASY_LINE[..80]).unwrap_err().column, 81);
+        assert_eq!(from_line(&format!("{EASY_LINE}1")).unwrap_err().column, 82);
+        assert_eq!(
+            from_line(&format!("{EASY_LINE}easy")).unwrap_err().to_string(),
+            "line 1, column 82: a comment must be set off from the cells"
+        );
+    }
+
+    #[test]
+    fn reads_collections() {
+        let text = format!("# two puzzles\n{EASY_LINE}\r\n\n  # the same, less one clue\n{}\n", EASY_LINE.replacen('5', ".", 1));
+        let grids = from_lines(&text).unwrap();
+        assert_eq!(grids.len(), 2);
+        assert_eq!(grids[1][0][0], 0);
+        assert_eq!(from_lines(&to_lines(&grids)), Ok(grids));
+
+        assert_eq!(from_lines(&format!("{EASY_LINE}\n\n{}\n", &EASY_LINE[1..])).unwrap_err().line, 3);
+    }
+
+    #[test]
+    fn reads_and_writes_sdk_and_ss() {
+        let grid = EASY;
+        let sdk = format!("#ASomeone\n#DEasy\n{}", to_sdk(&grid));
+        assert_eq!(from_sdk(&sdk), Ok(grid));
+        assert_eq!(from_sdk(&format!("[Puzzle]\r\n{}\n[State]\n{}", to_sdk(&grid), to_sdk(&grid))), Ok(grid));
+        assert_eq!(
+            from_sdk(&sdk.replacen("53..7", "53.x7", 1)),
+            Err(ParseError { line: 3, column: 4, reason: "expected a digit, '.' or a separator" })
+        );
+
+        let ss = to_ss(&grid);
+        assert_eq!(ss.lines().next(), Some("53.|.7.|..."));
+        assert_eq!(ss.lines().nth(3), Some("-----------"));
+        assert_eq!(from_ss(&ss), Ok(grid));
+        let framed: String = ss.lines().map(|line| format!("|{line}|\n")).collect();
+        assert_eq!(from_ss(&format!("*-----------*\n{framed}*-----------*\n")), Ok(grid));
+    }
+
+    #[test]
+    fn reads_and_writes_pencil_marks() {
+        let candidates = |sudoku: &Sudoku| (0..81)
+            .map(|i| sudoku.cell(i / 9, i % 9).possible_values())
+            .collect::<Vec<_>>();
+        let sudoku = Sudoku::from(EASY).unwrap()
+            .without(0, 2, 4)
+            .without(8, 0, 1);
+        for text in [to_ss_pencil_marks(&sudoku), to_hodoku_pencil_marks(&sudoku)] {
+            assert_eq!(candidates(&from_pencil_marks(&text).unwrap()), candidates(&sudoku));
+        }
+
+        let text = to_hodoku_pencil_marks(&sudoku);
+        assert!(text.starts_with(".---"));
+        let row: Vec<&str> = text.lines().nth(1).unwrap().split_whitespace().take(4).collect();
+        assert_eq!(row, ["|", "5", "3", "12"]);
+        assert!(to_ss_pencil_marks(&sudoku).starts_with("*---"));
+
+        let err = from_pencil_marks(&text.replacen(" 12 ", " 1x ", 1)).unwrap_err();
+        assert_eq!((err.line, err.reason), (2, "expected a candidate or a separator"));
+        let err = from_pencil_marks(&text.replacen(" 12 ", " 11 ", 1)).unwrap_err();
+        assert_eq!(err.reason, "a cell has each candidate once");
+        let err = from_pencil_marks(&text.replacen(" 12 ", " 10 ", 1)).unwrap_err();
+        assert_eq!(err.reason, "a cell without candidates is a lone 0");
+
+        // a contradiction still reads back
+        let emptied = sudoku.without(8, 0, 2).without(8, 0, 3);
+        assert_eq!(emptied.cell(8, 0), Cell::Invalid(None));
+        for text in [to_ss_pencil_marks(&emptied), to_hodoku_pencil_marks(&emptied)] {
+            assert!(text.lines().nth(11).unwrap().starts_with("| 0 "));
+            let read = from_pencil_marks(&text).unwrap();
+            assert_eq!(read.cell(8, 0), Cell::Invalid(None));
+            assert_eq!(candidates(&read), candidates(&emptied));
+        }
+    }
+
+    #[test]
+    fn round_trips_candidates() {
+        let sudoku = Sudoku::from(EASY).unwrap()
+            .without(0, 2, 4)
+            .without(0, 2, 1)
+            .set(0, 3, 9)
+            .without(8, 0, 1)
+            .without(8, 0, 2)
+            .without(8, 0, 3);
+        let text = to_candidates(&sudoku);
+        assert!(text.starts_with("+---"));
+        let row: Vec<&str> = text.lines().nth(1).unwrap().split_whitespace().take(5).collect();
+        assert_eq!(row, ["|", "5", "3", "{2}", "|"]);
+        assert!(text.lines().nth(1).unwrap().contains("!9"));
+        assert!(text.lines().nth(11).unwrap().starts_with("| {} "));
+        assert_eq!(from_candidates(&text), Ok(sudoku));
+        assert_eq!(from_candidates(&text.replace('\n', "\r\n")), Ok(sudoku));
+
+        let err = from_candidates(&text.replacen("{2}", "{2", 1)).unwrap_err();
+        assert_eq!((err.line, err.reason), (2, "expected a candidate or '}'"));
+        let err = from_candidates(&text.replacen("{2}", "{22}", 1)).unwrap_err();
+        assert_eq!(err.reason, "a cell has each candidate once");
+        assert_eq!(from_candidates(&text.replacen("!9", "?9", 1)).unwrap_err().reason,
+            "expected a digit, '{', '!' or a separator");
+    }
+
+    #[test]
+    fn reads_and_writes_hodoku_lines() {
+        let givens = EASY;
+        let state = Sudoku::from(givens).unwrap()
+            .set(0, 2, 4)
+            .without(8, 0, 1);
+        let line = to_hodoku(&givens, &state);
+        assert!(line.starts_with(":0000:x:53+4.7....6"));
+        assert!(line.ends_with(":191::"));
+        assert_eq!(from_hodoku(&line), Ok((givens, state)));
+
+        assert_eq!(from_hodoku(EASY_LINE).unwrap_err().column, 1);
+        assert_eq!(
+            from_hodoku(":0000:x:53..7:::"),
+            Err(ParseError { line: 1, column: 14, reason: "a puzzle needs 81 cells" })
+        );
+        assert_eq!(from_hodoku(&line.replacen(".7", "57", 1)).unwrap_err().reason, "the digits break the rules");
+        assert_eq!(from_hodoku(&line.replace(":191:", ":19:")).unwrap_err().reason,
+            "a deleted candidate is written as its digit, row and column");
+    }
+}
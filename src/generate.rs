@@ -0,0 +1,144 @@
+use crate::wavefunc;
+
+// xorshift64*, small and reproducible across platforms and crate versions so
+// that a seed always yields the same puzzle.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn seeded(seed: u64) -> Rng {
+        // xorshift must never be in the all-zero state
+        Rng((seed ^ 0x9e3779b97f4a7c15) | 1)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545f4914f6cdd1d)
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Puzzle {
+    pub puzzle: [[u8; 9]; 9],
+    pub solution: [[u8; 9]; 9],
+}
+
+pub struct Generator {
+    rng: Rng,
+}
+
+impl Generator {
+    pub fn new(seed: u64) -> Generator {
+        Generator { rng: Rng::seeded(seed) }
+    }
+
+    pub fn full_grid(&mut self) -> [[u8; 9]; 9] {
+        // the three boxes on the diagonal share no row or column, so any
+        // permutation in each of them is consistent
+        let mut grid = [[0u8; 9]; 9];
+        for zone in 0..3 {
+            let mut digits: Vec<u8> = (1..=9).collect();
+            self.rng.shuffle(&mut digits);
+            for (i, digit) in digits.into_iter().enumerate() {
+                grid[3 * zone + i / 3][3 * zone + i % 3] = digit;
+            }
+        }
+        let grid = wavefunc::Sudoku::from(grid).unwrap()
+            .solved()
+            .expect("diagonal boxes always extend to a full grid")
+            .grid();
+        // the solver fills in the other boxes much the same way every time,
+        // so relabel the digits and shuffle the bands, the stacks and the
+        // rows and columns within them, which keeps the grid valid
+        let mut digits: Vec<u8> = (1..=9).collect();
+        self.rng.shuffle(&mut digits);
+        let rows = self.shuffled_lines();
+        let cols = self.shuffled_lines();
+        std::array::from_fn(|r| std::array::from_fn(|c| digits[grid[rows[r]][cols[c]] as usize - 1]))
+    }
+
+    // The nine rows or columns in a random order that keeps the three of
+    // each band or stack together.
+    fn shuffled_lines(&mut self) -> Vec<usize> {
+        let mut bands = [0, 1, 2];
+        self.rng.shuffle(&mut bands);
+        let mut lines = Vec::new();
+        for band in bands {
+            let mut within = [0, 1, 2];
+            self.rng.shuffle(&mut within);
+            lines.extend(within.map(|i| 3 * band + i));
+        }
+        lines
+    }
+
+    // Removes clues in random order, putting back every clue whose removal
+    // would allow a second solution.
+    pub fn puzzle(&mut self) -> Puzzle {
+        let solution = self.full_grid();
+        let mut puzzle = solution;
+        let mut cells: Vec<(usize, usize)> = (0..9)
+            .flat_map(|r| (0..9).map(move |c| (r, c)))
+            .collect();
+        self.rng.shuffle(&mut cells);
+
+        for (r, c) in cells {
+            let clue = puzzle[r][c];
+            puzzle[r][c] = 0;
+            if !wavefunc::Sudoku::from(puzzle).unwrap().is_unique() {
+                puzzle[r][c] = clue;
+            }
+        }
+        Puzzle { puzzle, solution }
+    }
+}
+
+pub fn generate(seed: u64) -> Puzzle {
+    Generator::new(seed).puzzle()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_puzzle_is_unique() {
+        let Puzzle { puzzle, solution } = generate(7);
+        let sudoku = wavefunc::Sudoku::from(puzzle).unwrap();
+        assert!(sudoku.is_unique());
+        assert_eq!(sudoku.solved().unwrap().grid(), solution);
+        assert!(puzzle.iter().flatten().filter(|&&n| n != 0).count() < 40);
+    }
+
+    #[test]
+    fn seed_is_reproducible() {
+        assert_eq!(Generator::new(3).full_grid(), Generator::new(3).full_grid());
+        assert_ne!(Generator::new(3).full_grid(), Generator::new(4).full_grid());
+    }
+
+    #[test]
+    fn full_grids_vary_outside_the_diagonal_boxes() {
+        let grids: Vec<_> = (0..200).map(|seed| Generator::new(seed).full_grid()).collect();
+        for grid in &grids {
+            assert!(wavefunc::Sudoku::from(*grid).unwrap().is_solved());
+        }
+        // every cell, including those the solver fills in, takes every digit
+        for r in 0..9 {
+            for c in 0..9 {
+                let seen = grids.iter().fold(0u16, |seen, grid| seen | 1 << grid[r][c]);
+                assert_eq!(seen.count_ones(), 9, "cell ({r}, {c})");
+            }
+        }
+    }
+}
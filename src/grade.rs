@@ -0,0 +1,132 @@
+use std::fmt;
+use crate::error::SudokuError;
+use crate::logic::{self, Technique};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+    Expert,
+    Diabolical,
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Difficulty::*;
+        let name = match self {
+            Easy => "easy",
+            Medium => "medium",
+            Hard => "hard",
+            Expert => "expert",
+            Diabolical => "diabolical",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Grade {
+    pub difficulty: Difficulty,
+    pub score: u32,
+    // `None` when no deduction was needed or none could be made at all
+    pub hardest: Option<Technique>,
+}
+
+// How many times the hardest technique may be needed before the puzzle is
+// bumped to the next difficulty.
+const REPEAT_THRESHOLD: usize = 4;
+
+// Added to the score for every cell the techniques could not fill in.
+const UNRESOLVED_CELL_WEIGHT: u32 = 50;
+
+fn weight(technique: Technique) -> u32 {
+    use Technique::*;
+    match technique {
+        NakedSingle => 1,
+        HiddenSingle => 2,
+        LockedCandidates => 5,
+        NakedPair => 10,
+        HiddenPair => 15,
+        NakedTriple => 20,
+        HiddenTriple => 25,
+        NakedQuad => 35,
+        HiddenQuad => 40,
+    }
+}
+
+fn base_difficulty(technique: Technique) -> Difficulty {
+    use Technique::*;
+    match technique {
+        NakedSingle | HiddenSingle => Difficulty::Easy,
+        LockedCandidates => Difficulty::Medium,
+        NakedPair | HiddenPair => Difficulty::Hard,
+        NakedTriple | HiddenTriple | NakedQuad | HiddenQuad => Difficulty::Expert,
+    }
+}
+
+// Grades by the hardest technique the logical solver needs, one level up if
+// that technique is needed repeatedly. Puzzles the techniques can't finish
+// (including those without a solution) are diabolical.
+pub fn grade(grid: [[u8; 9]; 9]) -> Result<Grade, SudokuError> {
+    let deduction = logic::solve(grid)?;
+    let hardest = deduction.steps.iter().map(|step| step.technique).max();
+
+    let unresolved = deduction.sudoku.grid().iter()
+        .flatten()
+        .filter(|&&val| val == 0)
+        .count() as u32;
+    let score = deduction.steps.iter()
+        .map(|step| weight(step.technique))
+        .sum::<u32>()
+        + unresolved * UNRESOLVED_CELL_WEIGHT;
+
+    let difficulty = match hardest {
+        _ if !deduction.is_solved() => Difficulty::Diabolical,
+        None => Difficulty::Easy,
+        Some(technique) => {
+            let uses = deduction.steps.iter()
+                .filter(|step| step.technique == technique)
+                .count();
+            let difficulty = base_difficulty(technique);
+            if uses >= REPEAT_THRESHOLD && difficulty > Difficulty::Easy && difficulty < Difficulty::Expert {
+                next(difficulty)
+            } else {
+                difficulty
+            }
+        },
+    };
+
+    Ok(Grade { difficulty, score, hardest })
+}
+
+fn next(difficulty: Difficulty) -> Difficulty {
+    use Difficulty::*;
+    match difficulty {
+        Easy => Medium,
+        Medium => Hard,
+        Hard => Expert,
+        Expert | Diabolical => Diabolical,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::EASY;
+
+    #[test]
+    fn singles_only_is_easy() {
+        let grade = grade(EASY).unwrap();
+        assert_eq!(grade.difficulty, Difficulty::Easy);
+        assert!(grade.hardest <= Some(Technique::HiddenSingle));
+        assert!(grade.score >= 51);
+    }
+
+    #[test]
+    fn empty_grid_is_diabolical() {
+        let grade = grade([[0u8; 9]; 9]).unwrap();
+        assert_eq!(grade.difficulty, Difficulty::Diabolical);
+        assert_eq!(grade.score, 81 * UNRESOLVED_CELL_WEIGHT);
+    }
+}
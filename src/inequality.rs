@@ -0,0 +1,216 @@
+use crate::error::SudokuError;
+use crate::rules::{self, Constraint, Pair};
+use crate::wavefunc::{BitSet, Sudoku};
+
+// The `<` and `>` markers of greater-than sudoku, each as the pair of
+// orthogonally adjacent cells of a box it sits between, smaller cell first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inequalities {
+    pairs: Vec<Pair>,
+}
+
+impl Inequalities {
+    pub fn new(pairs: Vec<Pair>) -> Result<Inequalities, SudokuError> {
+        for &(a, b) in &pairs {
+            if let Some(&(row, col)) = [a, b].iter().find(|&&(r, c)| r >= 9 || c >= 9) {
+                return Err(SudokuError::IndexOutOfRange { row, col, size: 9 });
+            }
+            if a.0.abs_diff(b.0) + a.1.abs_diff(b.1) != 1 {
+                return Err(SudokuError::InvalidConstraint { reason: "an inequality must sit between orthogonally adjacent cells" });
+            }
+            if (a.0 / 3, a.1 / 3) != (b.0 / 3, b.1 / 3) {
+                return Err(SudokuError::InvalidConstraint { reason: "an inequality may not cross the border of a box" });
+            }
+        }
+        Ok(Inequalities { pairs })
+    }
+
+    // Reads a grid drawn with its markers, such as
+    //
+    //     5 < 7 > 4 | 6 ...
+    //     ^       v
+    //     6 > 1 < 2 | ...
+    //
+    // Cells are digits, with `.` or `0` for empty ones. `<` and `>` go
+    // between cells of a row, and `^` and `v` on a line of their own, under
+    // the upper of two cells. Every marker points at the smaller cell. Bars
+    // between cells are ignored, and so are blank lines and lines drawn with
+    // only bars, dashes and plus signs.
+    pub fn parse(text: &str) -> Result<([[u8; 9]; 9], Inequalities), SudokuError> {
+        let mut grid = [[0u8; 9]; 9];
+        let mut pairs = Vec::new();
+        let mut row = 0;
+        // the text columns of the cells of the last row
+        let mut columns: Vec<usize> = Vec::new();
+        // `^` and `v` markers waiting for the row below, by text column
+        let mut markers: Vec<(usize, char)> = Vec::new();
+        for line in text.lines() {
+            let chars: Vec<char> = line.chars().collect();
+            if chars.iter().all(|&ch| ch.is_whitespace() || "|-+".contains(ch)) { continue; }
+            if chars.iter().all(|&ch| ch.is_whitespace() || ch == '^' || ch == 'v') {
+                if row == 0 || !markers.is_empty() {
+                    return Err(SudokuError::InvalidConstraint { reason: "markers under a row must sit between two rows" });
+                }
+                markers.extend(chars.iter().copied().enumerate().filter(|&(_, ch)| !ch.is_whitespace()));
+                continue;
+            }
+            if row == 9 {
+                return Err(SudokuError::InvalidConstraint { reason: "a grid must have nine rows of nine cells" });
+            }
+
+            let mut cells = Vec::new();
+            let mut marker = None;
+            for (x, &ch) in chars.iter().enumerate() {
+                let value = match ch {
+                    '.' | '0' => 0,
+                    '1'..='9' => ch as u8 - b'0',
+                    '<' | '>' if marker.is_none() && !cells.is_empty() => { marker = Some(ch); continue; },
+                    _ if ch.is_whitespace() || ch == '|' => continue,
+                    _ => return Err(SudokuError::InvalidConstraint { reason: "unexpected character in an inequality grid" }),
+                };
+                let col = cells.len();
+                if col == 9 {
+                    return Err(SudokuError::InvalidConstraint { reason: "a grid must have nine rows of nine cells" });
+                }
+                match marker.take() {
+                    Some('<') => pairs.push(((row, col - 1), (row, col))),
+                    Some(_) => pairs.push(((row, col), (row, col - 1))),
+                    None => {},
+                }
+                grid[row][col] = value;
+                cells.push(x);
+            }
+            if cells.len() != 9 || marker.is_some() {
+                return Err(SudokuError::InvalidConstraint { reason: "a grid must have nine rows of nine cells" });
+            }
+            for (x, ch) in markers.drain(..) {
+                let col = columns.iter().position(|&c| c == x)
+                    .ok_or(SudokuError::InvalidConstraint { reason: "markers under a row must line up with its cells" })?;
+                let (upper, lower) = ((row - 1, col), (row, col));
+                pairs.push(if ch == '^' { (upper, lower) } else { (lower, upper) });
+            }
+            columns = cells;
+            row += 1;
+        }
+        if row != 9 || !markers.is_empty() {
+            return Err(SudokuError::InvalidConstraint { reason: "a grid must have nine rows of nine cells" });
+        }
+        Ok((grid, Inequalities::new(pairs)?))
+    }
+
+    pub fn pairs(&self) -> &[Pair] {
+        &self.pairs
+    }
+}
+
+impl Constraint for Inequalities {
+    fn is_valid(&self, sudoku: &Sudoku) -> bool {
+        let grid = sudoku.grid();
+        self.pairs.iter().all(|&((r1, c1), (r2, c2))| {
+            let (smaller, larger) = (grid[r1][c1], grid[r2][c2]);
+            smaller == 0 || larger == 0 || smaller < larger
+        })
+    }
+
+    // Keeps the smaller cell below the largest candidate of the larger one
+    // and the other way round. Repeating this until nothing changes carries
+    // the bounds along chains of markers.
+    fn prune(&self, sudoku: &Sudoku) -> Sudoku {
+        let mut sudoku = *sudoku;
+        for &(smaller, larger) in &self.pairs {
+            let low = sudoku.cell(smaller.0, smaller.1).possible_values();
+            let high = sudoku.cell(larger.0, larger.1).possible_values();
+            let (Some(least), Some(most)) = (low.iter().min(), high.iter().max()) else { continue; };
+            let allowed = [
+                low.iter().filter(|&n| n < most).fold(BitSet::new(), |set, n| set.insert(n)),
+                high.iter().filter(|&n| n > least).fold(BitSet::new(), |set, n| set.insert(n)),
+            ];
+            sudoku = rules::restrict(&sudoku, &[smaller, larger], &allowed);
+        }
+        sudoku
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::Rules;
+    use crate::test_util::SOLUTION;
+
+    // Draws `solution` with every marker inside the boxes and only the
+    // digits of `givens`.
+    fn drawn(solution: &[[u8; 9]; 9], givens: &[[u8; 9]; 9]) -> String {
+        let mut text = String::new();
+        for r in 0..9 {
+            for c in 0..9 {
+                text.push(if givens[r][c] == 0 { '.' } else { (b'0' + givens[r][c]) as char });
+                if c == 8 { break; }
+                text.push_str(match c % 3 {
+                    2 => " | ",
+                    _ if solution[r][c] < solution[r][c + 1] => " < ",
+                    _ => " > ",
+                });
+            }
+            text.push('\n');
+            if r == 8 { break; }
+            if r % 3 == 2 {
+                text.push_str("------+-----------+------\n");
+                continue;
+            }
+            let markers: String = (0..9)
+                .map(|c| if solution[r][c] < solution[r + 1][c] { "^   " } else { "v   " })
+                .collect();
+            text.push_str(markers.trim_end());
+            text.push('\n');
+        }
+        text
+    }
+
+    #[test]
+    fn parses_markers() {
+        let mut givens = [[0u8; 9]; 9];
+        givens[0][0] = 5;
+        let (grid, inequalities) = Inequalities::parse(&drawn(&SOLUTION, &givens)).unwrap();
+        assert_eq!(grid, givens);
+        // six per row and six per column
+        assert_eq!(inequalities.pairs().len(), 108);
+        assert!(inequalities.pairs().contains(&((0, 1), (0, 0))));
+        assert!(inequalities.pairs().contains(&((0, 0), (1, 0))));
+        assert!(inequalities.pairs().contains(&((1, 2), (0, 2))));
+
+        assert!(Inequalities::parse("1 < 2").is_err());
+        let across_boxes = drawn(&SOLUTION, &givens).replacen(" | ", " < ", 1);
+        assert_eq!(
+            Inequalities::parse(&across_boxes).map(|_| ()),
+            Err(SudokuError::InvalidConstraint { reason: "an inequality may not cross the border of a box" })
+        );
+        assert!(Inequalities::new(vec![((2, 0), (3, 0))]).is_err());
+        let dashed = drawn(&SOLUTION, &givens).replacen(" | ", " - ", 1);
+        assert!(Inequalities::parse(&dashed).is_err());
+        let misaligned = drawn(&SOLUTION, &givens).replacen("\n^", "\n ^", 1);
+        assert!(Inequalities::parse(&misaligned).is_err());
+    }
+
+    #[test]
+    fn propagates_bounds_along_chains() {
+        let inequalities = Inequalities::new(vec![((0, 0), (0, 1)), ((0, 1), (0, 2)), ((0, 2), (1, 2))]).unwrap();
+        let mut rules = Rules::standard();
+        rules.add_inequalities(inequalities);
+        let sudoku = Sudoku::from_rules([[0u8; 9]; 9], &rules).unwrap();
+        assert_eq!(sudoku.cell(0, 0).possible_values().iter().max(), Some(6));
+        assert_eq!(sudoku.cell(0, 2).possible_values().iter().min(), Some(3));
+        assert_eq!(sudoku.cell(1, 2).possible_values().iter().min(), Some(4));
+    }
+
+    #[test]
+    fn solves_greater_than_sudoku() {
+        let (grid, inequalities) = Inequalities::parse(&drawn(&SOLUTION, &[[0u8; 9]; 9])).unwrap();
+        let mut rules = Rules::standard();
+        rules.add_inequalities(inequalities.clone());
+        let solved = Sudoku::from_rules(grid, &rules).unwrap()
+            .solved_with(&rules)
+            .unwrap();
+        assert!(crate::error::validate(&solved.grid()).is_ok());
+        assert!(inequalities.is_valid(&solved));
+    }
+}
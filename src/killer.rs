@@ -0,0 +1,140 @@
+use crate::error::SudokuError;
+use crate::rules::{self, Constraint};
+use crate::wavefunc::{BitSet, Sudoku};
+
+// A killer cage: its cells hold distinct digits adding up to `sum`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cage {
+    sum: u8,
+    cells: Vec<(usize, usize)>,
+    // every set of distinct digits of the right size that adds up to `sum`
+    combinations: Vec<BitSet>,
+}
+
+impl Cage {
+    pub fn new(sum: u8, cells: Vec<(usize, usize)>) -> Result<Cage, SudokuError> {
+        if cells.is_empty() || cells.len() > 9 {
+            return Err(SudokuError::InvalidConstraint { reason: "a cage must have between 1 and 9 cells" });
+        }
+        if let Some(&(row, col)) = cells.iter().find(|&&(r, c)| r >= 9 || c >= 9) {
+            return Err(SudokuError::IndexOutOfRange { row, col, size: 9 });
+        }
+        if cells.iter().enumerate().any(|(i, cell)| cells[..i].contains(cell)) {
+            return Err(SudokuError::InvalidConstraint { reason: "a cage may not repeat a cell" });
+        }
+
+        let combinations: Vec<BitSet> = (0u32..1 << 9)
+            .filter(|mask| mask.count_ones() as usize == cells.len())
+            .map(|mask| (1..=9u8)
+                .filter(|n| mask & 1 << (n - 1) != 0)
+                .fold(BitSet::new(), |set, n| set.insert(n)))
+            .filter(|set| set.iter().map(u32::from).sum::<u32>() == sum as u32)
+            .collect();
+        if combinations.is_empty() {
+            return Err(SudokuError::InvalidConstraint { reason: "no distinct digits fill the cage to its sum" });
+        }
+
+        Ok(Cage { sum, cells, combinations })
+    }
+
+    pub fn sum(&self) -> u8 {
+        self.sum
+    }
+
+    pub fn cells(&self) -> &[(usize, usize)] {
+        &self.cells
+    }
+}
+
+impl Constraint for Cage {
+    fn peers(&self, row: usize, col: usize) -> Vec<(usize, usize)> {
+        if !self.cells.contains(&(row, col)) { return Vec::new(); }
+        self.cells.clone()
+    }
+
+    fn is_valid(&self, sudoku: &Sudoku) -> bool {
+        let known: Vec<u8> = self.cells.iter()
+            .map(|&(r, c)| sudoku.grid()[r][c])
+            .filter(|&n| n != 0)
+            .collect();
+        let total: u32 = known.iter().map(|&n| n as u32).sum();
+        if known.len() == self.cells.len() { total == self.sum as u32 }
+        else { total < self.sum as u32 }
+    }
+
+    // Keeps only the candidates that appear in some combination the cells
+    // can still be filled with.
+    fn prune(&self, sudoku: &Sudoku) -> Sudoku {
+        let candidates: Vec<BitSet> = self.cells.iter()
+            .map(|&(r, c)| sudoku.cell(r, c).possible_values())
+            .collect();
+        let possible: Vec<BitSet> = self.combinations.iter()
+            .copied()
+            .filter(|&digits| rules::fits(&candidates, digits))
+            .collect();
+
+        let mut sudoku = *sudoku;
+        for (&(r, c), cell) in self.cells.iter().zip(&candidates) {
+            let allowed = possible.iter()
+                .fold(BitSet::new(), |allowed, &digits| allowed.union(cell.intersection(digits)));
+            for n in cell.iter().filter(|&n| !allowed.contains(n)) {
+                sudoku = sudoku.without(r, c, n);
+            }
+        }
+        sudoku
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::Rules;
+    use crate::test_util::SOLUTION;
+
+    #[test]
+    fn prunes_to_cage_combinations() {
+        let mut rules = Rules::standard();
+        rules.add_cage(Cage::new(3, vec![(0, 0), (4, 4)]).unwrap());
+        let sudoku = Sudoku::from_rules([[0u8; 9]; 9], &rules).unwrap();
+        let expected = BitSet::new().insert(1).insert(2);
+        assert_eq!(sudoku.cell(0, 0).possible_values(), expected);
+        assert_eq!(sudoku.cell(4, 4).possible_values(), expected);
+        assert_eq!(sudoku.cell(0, 1).possible_values(), BitSet::full());
+    }
+
+    #[test]
+    fn rejects_impossible_cages() {
+        assert!(Cage::new(2, vec![(0, 0), (0, 1)]).is_err());
+        assert!(Cage::new(5, vec![(0, 0), (0, 0)]).is_err());
+        assert_eq!(
+            Cage::new(5, vec![(0, 9)]),
+            Err(SudokuError::IndexOutOfRange { row: 0, col: 9, size: 9 })
+        );
+    }
+
+    #[test]
+    #[allow(clippy::needless_range_loop)]
+    fn solves_cages_without_clues() {
+        // dominoes along each row, with the last column caged on its own
+        let mut rules = Rules::standard();
+        for row in 0..9 {
+            for col in (0..8).step_by(2) {
+                let sum = SOLUTION[row][col] + SOLUTION[row][col + 1];
+                rules.add_cage(Cage::new(sum, vec![(row, col), (row, col + 1)]).unwrap());
+            }
+            rules.add_cage(Cage::new(SOLUTION[row][8], vec![(row, 8)]).unwrap());
+        }
+
+        let solved = Sudoku::from_rules([[0u8; 9]; 9], &rules).unwrap()
+            .solved_with(&rules)
+            .unwrap();
+        let grid = solved.grid();
+        assert!(crate::error::validate(&grid).is_ok());
+        for row in 0..9 {
+            for col in (0..8).step_by(2) {
+                assert_eq!(grid[row][col] + grid[row][col + 1], SOLUTION[row][col] + SOLUTION[row][col + 1]);
+            }
+            assert_eq!(grid[row][8], SOLUTION[row][8]);
+        }
+    }
+}
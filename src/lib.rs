@@ -3,4 +3,24 @@
 pub mod backtrack;
 pub mod sets;
 pub mod bits;
-pub mod wavefunc;
\ No newline at end of file
+pub mod wavefunc;
+pub mod dlx;
+pub mod sat;
+pub mod solver;
+pub mod error;
+pub mod generate;
+pub mod logic;
+pub mod grade;
+pub mod rules;
+pub mod regions;
+pub mod killer;
+pub mod lines;
+pub mod outside;
+pub mod dots;
+pub mod samurai;
+pub mod inequality;
+pub mod format;
+pub mod parse;
+
+#[cfg(test)]
+mod test_util;
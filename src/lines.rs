@@ -0,0 +1,344 @@
+use std::fmt;
+use crate::error::SudokuError;
+use crate::rules::{self, Constraint};
+use crate::wavefunc::{BitSet, Sudoku};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKind {
+    // strictly increasing from the bulb, the first cell
+    Thermo,
+    // neighbouring cells differ by at least 5
+    Whisper,
+    // a set of distinct consecutive digits, in any order
+    Renban,
+    // reads the same from either end
+    Palindrome,
+}
+
+impl LineKind {
+    pub fn name(&self) -> &'static str {
+        use LineKind::*;
+        match self {
+            Thermo => "thermo",
+            Whisper => "whisper",
+            Renban => "renban",
+            Palindrome => "palindrome",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<LineKind> {
+        use LineKind::*;
+        [Thermo, Whisper, Renban, Palindrome].into_iter()
+            .find(|kind| kind.name() == name)
+    }
+}
+
+impl fmt::Display for LineKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+// A path of cells, each one next to the one before it, orthogonally or
+// diagonally.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    kind: LineKind,
+    cells: Vec<(usize, usize)>,
+}
+
+impl Line {
+    pub fn new(kind: LineKind, cells: Vec<(usize, usize)>) -> Result<Line, SudokuError> {
+        if cells.len() < 2 {
+            return Err(SudokuError::InvalidConstraint { reason: "a line must have at least two cells" });
+        }
+        if let Some(&(row, col)) = cells.iter().find(|&&(r, c)| r >= 9 || c >= 9) {
+            return Err(SudokuError::IndexOutOfRange { row, col, size: 9 });
+        }
+        if cells.iter().enumerate().any(|(i, cell)| cells[..i].contains(cell)) {
+            return Err(SudokuError::InvalidConstraint { reason: "a line may not repeat a cell" });
+        }
+        if cells.windows(2).any(|pair| pair[0].0.abs_diff(pair[1].0) > 1 || pair[0].1.abs_diff(pair[1].1) > 1) {
+            return Err(SudokuError::InvalidConstraint { reason: "each cell of a line must touch the one before it" });
+        }
+        if matches!(kind, LineKind::Thermo | LineKind::Renban) && cells.len() > 9 {
+            return Err(SudokuError::InvalidConstraint { reason: "thermos and renbans hold distinct digits, so at most nine cells" });
+        }
+        Ok(Line { kind, cells })
+    }
+
+    // Reads one line per row of text: its kind followed by its cells from
+    // one end to the other, as in `thermo r1c1 r2c2 r3c2`. Rows and columns
+    // count from 1 here, and `#` starts a comment.
+    pub fn parse(text: &str) -> Result<Vec<Line>, SudokuError> {
+        let mut lines = Vec::new();
+        for (_, row) in rules::clue_rows(text) {
+            let mut words = row.split_whitespace();
+            let Some(name) = words.next() else { continue; };
+            let kind = LineKind::from_name(name)
+                .ok_or(SudokuError::InvalidConstraint { reason: "unknown kind of line" })?;
+            let cells = words.map(rules::parse_cell)
+                .collect::<Option<Vec<_>>>()
+                .ok_or(SudokuError::InvalidConstraint { reason: "cells must be written like r1c1" })?;
+            lines.push(Line::new(kind, cells)?);
+        }
+        Ok(lines)
+    }
+
+    pub fn kind(&self) -> LineKind {
+        self.kind
+    }
+
+    pub fn cells(&self) -> &[(usize, usize)] {
+        &self.cells
+    }
+
+    fn values(&self, sudoku: &Sudoku) -> Vec<u8> {
+        let grid = sudoku.grid();
+        self.cells.iter().map(|&(r, c)| grid[r][c]).collect()
+    }
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        for (r, c) in &self.cells {
+            write!(f, " r{}c{}", r + 1, c + 1)?;
+        }
+        Ok(())
+    }
+}
+
+fn digits(from: u8, to: u8) -> BitSet {
+    (from..=to).fold(BitSet::new(), |set, n| set.insert(n))
+}
+
+impl Constraint for Line {
+    fn peers(&self, row: usize, col: usize) -> Vec<(usize, usize)> {
+        let Some(i) = self.cells.iter().position(|&cell| cell == (row, col)) else { return Vec::new(); };
+        match self.kind {
+            LineKind::Thermo | LineKind::Renban => self.cells.clone(),
+            LineKind::Whisper => {
+                let mut peers = Vec::new();
+                if i > 0 { peers.push(self.cells[i - 1]); }
+                if i + 1 < self.cells.len() { peers.push(self.cells[i + 1]); }
+                peers
+            },
+            LineKind::Palindrome => Vec::new(),
+        }
+    }
+
+    fn is_valid(&self, sudoku: &Sudoku) -> bool {
+        let values = self.values(sudoku);
+        let known: Vec<(usize, u8)> = values.iter()
+            .copied()
+            .enumerate()
+            .filter(|&(_, n)| n != 0)
+            .collect();
+        match self.kind {
+            // later cells need room for the ones in between
+            LineKind::Thermo => known.windows(2)
+                .all(|pair| pair[1].1 as usize >= pair[0].1 as usize + (pair[1].0 - pair[0].0)),
+            LineKind::Whisper => values.windows(2)
+                .all(|pair| pair[0] == 0 || pair[1] == 0 || pair[0].abs_diff(pair[1]) >= 5),
+            LineKind::Renban => {
+                let lowest = known.iter().map(|&(_, n)| n).min().unwrap_or(0);
+                let highest = known.iter().map(|&(_, n)| n).max().unwrap_or(0);
+                ((highest - lowest) as usize) < values.len()
+            },
+            LineKind::Palindrome => (0..values.len() / 2).all(|i| {
+                let (a, b) = (values[i], values[values.len() - 1 - i]);
+                a == 0 || b == 0 || a == b
+            }),
+        }
+    }
+
+    fn prune(&self, sudoku: &Sudoku) -> Sudoku {
+        let candidates: Vec<BitSet> = self.cells.iter()
+            .map(|&(r, c)| sudoku.cell(r, c).possible_values())
+            .collect();
+        let len = candidates.len();
+        let allowed: Vec<BitSet> = match self.kind {
+            // each cell lies above the lowest the cells before it allow and
+            // below the highest the cells after it allow
+            LineKind::Thermo => {
+                let mut lowest = vec![0u8; len];
+                let mut floor = 0;
+                for i in 0..len {
+                    floor = candidates[i].iter().find(|&n| n > floor).unwrap_or(10);
+                    lowest[i] = floor;
+                }
+                let mut ceiling = 10;
+                (0..len).rev()
+                    .map(|i| {
+                        ceiling = candidates[i].iter().filter(|&n| n < ceiling).last().unwrap_or(0);
+                        if lowest[i] <= ceiling { digits(lowest[i], ceiling) } else { BitSet::new() }
+                    })
+                    .collect::<Vec<_>>()
+                    .into_iter()
+                    .rev()
+                    .collect()
+            },
+            LineKind::Whisper => (0..len).map(|i| {
+                let neighbours: Vec<BitSet> = [i.checked_sub(1), Some(i + 1).filter(|&j| j < len)]
+                    .into_iter()
+                    .flatten()
+                    .map(|j| candidates[j])
+                    .collect();
+                candidates[i].iter()
+                    .filter(|&n| neighbours.iter().all(|other| other.iter().any(|m| n.abs_diff(m) >= 5)))
+                    .fold(BitSet::new(), |set, n| set.insert(n))
+            }).collect(),
+            // the union of every run of consecutive digits the cells can
+            // still be filled with
+            LineKind::Renban => {
+                let runs: Vec<BitSet> = (1..=10 - len as u8)
+                    .map(|start| digits(start, start + len as u8 - 1))
+                    .filter(|&run| rules::fits(&candidates, run))
+                    .collect();
+                candidates.iter()
+                    .map(|cell| runs.iter().fold(BitSet::new(), |set, &run| set.union(cell.intersection(run))))
+                    .collect()
+            },
+            LineKind::Palindrome => (0..len)
+                .map(|i| candidates[i].intersection(candidates[len - 1 - i]))
+                .collect(),
+        };
+        rules::restrict(sudoku, &self.cells, &allowed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::Rules;
+    use crate::test_util;
+
+    // The grid with the digits of `givens` in place.
+    fn with(givens: &[(usize, usize, u8)]) -> [[u8; 9]; 9] {
+        let mut grid = [[0u8; 9]; 9];
+        for &(r, c, n) in givens {
+            grid[r][c] = n;
+        }
+        grid
+    }
+
+    #[test]
+    fn parses_lines() {
+        let lines = Line::parse("
+            # a thermo and a whisper
+            thermo r1c1 r1c2 r2c3
+            Whisper R9C9 r8c8   # diagonal
+        ").unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].kind(), LineKind::Thermo);
+        assert_eq!(lines[0].cells(), [(0, 0), (0, 1), (1, 2)]);
+        assert_eq!(lines[1].to_string(), "whisper r9c9 r8c8");
+
+        assert!(Line::parse("snake r1c1 r1c2").is_err());
+        assert!(Line::parse("thermo r1c1 r1c3").is_err());
+        assert!(Line::parse("renban r1c1 r0c2").is_err());
+        assert!(Line::parse("thermo r1c1 one").is_err());
+    }
+
+    #[test]
+    fn rejects_impossible_shapes() {
+        let reason = |kind, cells| match Line::new(kind, cells) {
+            Err(SudokuError::InvalidConstraint { reason }) => reason,
+            other => panic!("{other:?}"),
+        };
+        assert_eq!(reason(LineKind::Whisper, vec![(0, 0)]), "a line must have at least two cells");
+        assert_eq!(reason(LineKind::Palindrome, vec![(0, 0), (1, 1), (0, 0)]), "a line may not repeat a cell");
+        let snake: Vec<_> = (0..9).map(|c| (0, c)).chain([(1, 8)]).collect();
+        assert!(Line::new(LineKind::Palindrome, snake.clone()).is_ok());
+        assert_eq!(reason(LineKind::Renban, snake), "thermos and renbans hold distinct digits, so at most nine cells");
+        assert_eq!(
+            Line::new(LineKind::Thermo, vec![(8, 8), (9, 8)]),
+            Err(SudokuError::IndexOutOfRange { row: 9, col: 8, size: 9 })
+        );
+    }
+
+    #[test]
+    fn thermo_leaves_room_on_both_sides() {
+        let sudoku = test_util::pruned(Line::parse("thermo r1c1 r1c2 r1c3").unwrap(), [[0u8; 9]; 9]);
+        assert_eq!(sudoku.cell(0, 0).possible_values(), digits(1, 7));
+        assert_eq!(sudoku.cell(0, 2).possible_values(), digits(3, 9));
+
+        // a given in the middle splits the range
+        let sudoku = test_util::pruned(Line::parse("thermo r1c1 r2c2 r3c3").unwrap(), with(&[(1, 1, 5)]));
+        assert_eq!(sudoku.cell(0, 0).possible_values(), digits(1, 4));
+        assert_eq!(sudoku.cell(2, 2).possible_values(), digits(6, 9));
+
+        // nine cells long, every cell is forced
+        let sudoku = test_util::pruned(Line::parse("thermo r9c1 r9c2 r9c3 r9c4 r9c5 r9c6 r9c7 r9c8 r9c9").unwrap(), [[0u8; 9]; 9]);
+        assert!((0..9).all(|c| sudoku.cell(8, c).possible_values() == BitSet::from_single(c as u8 + 1)));
+
+        // increasing is not enough when the cells between can't fit
+        let thermo = &Line::parse("thermo r1c1 r2c2 r3c3").unwrap()[0];
+        assert!(!thermo.is_valid(&Sudoku::from(with(&[(0, 0, 2), (2, 2, 3)])).unwrap()));
+        assert!(thermo.is_valid(&Sudoku::from(with(&[(0, 0, 2), (2, 2, 4)])).unwrap()));
+    }
+
+    #[test]
+    fn whisper_never_holds_a_five() {
+        let sudoku = test_util::pruned(Line::parse("whisper r5c5 r5c6").unwrap(), [[0u8; 9]; 9]);
+        assert!(!sudoku.cell(4, 4).possible_values().contains(5));
+        assert_eq!(sudoku.cell(4, 5).possible_values().size(), 8);
+
+        // next to a 3 only 8 and 9 are far enough apart
+        let sudoku = test_util::pruned(Line::parse("whisper r1c1 r2c2 r3c1").unwrap(), with(&[(1, 1, 3)]));
+        assert_eq!(sudoku.cell(0, 0).possible_values(), digits(8, 9));
+        assert_eq!(sudoku.cell(2, 0).possible_values(), digits(8, 9));
+    }
+
+    #[test]
+    fn renban_keeps_to_one_run() {
+        let sudoku = test_util::pruned(Line::parse("renban r1c1 r2c2 r3c3").unwrap(), with(&[(0, 0, 2)]));
+        assert_eq!(sudoku.cell(1, 1).possible_values(), digits(1, 4).remove(2));
+
+        // nine cells take every digit, so nothing is ruled out
+        let sudoku = test_util::pruned(Line::parse("renban r5c1 r5c2 r5c3 r5c4 r5c5 r5c6 r5c7 r5c8 r5c9").unwrap(), [[0u8; 9]; 9]);
+        assert!((0..9).all(|c| sudoku.cell(4, c).possible_values() == digits(1, 9)));
+
+        let renban = &Line::parse("renban r1c1 r1c2 r1c3").unwrap()[0];
+        assert!(!renban.is_valid(&Sudoku::from(with(&[(0, 0, 1), (0, 2, 4)])).unwrap()));
+        assert!(renban.is_valid(&Sudoku::from(with(&[(0, 0, 1), (0, 2, 3)])).unwrap()));
+    }
+
+    #[test]
+    fn palindrome_mirrors_its_ends() {
+        let sudoku = test_util::pruned(Line::parse("palindrome r9c9 r8c8 r7c9 r6c8").unwrap(), with(&[(8, 8, 7)]));
+        assert_eq!(sudoku.cell(5, 7).possible_values(), BitSet::from_single(7));
+
+        // the middle of an odd palindrome has no partner
+        let palindrome = &Line::parse("palindrome r3c3 r4c4 r5c5").unwrap()[0];
+        assert!(palindrome.peers(3, 3).is_empty());
+        assert!(!palindrome.is_valid(&Sudoku::from(with(&[(2, 2, 1), (4, 4, 2)])).unwrap()));
+        assert!(palindrome.is_valid(&Sudoku::from(with(&[(2, 2, 1), (4, 4, 1)])).unwrap()));
+    }
+
+    #[test]
+    fn solves_with_lines() {
+        let text = "
+            thermo r1c1 r2c2 r3c3 r4c4 r5c5
+            whisper r9c1 r8c2 r7c3 r6c4
+            renban r1c9 r2c9 r3c9 r4c9
+            palindrome r5c6 r6c7 r7c8 r8c9
+        ";
+        let lines = Line::parse(text).unwrap();
+        let mut rules = Rules::standard();
+        for line in &lines {
+            rules.add_line(line.clone());
+        }
+        let solved = Sudoku::from_rules([[0u8; 9]; 9], &rules).unwrap()
+            .solved_with(&rules)
+            .unwrap();
+        assert!(crate::error::validate(&solved.grid()).is_ok());
+        for line in &lines {
+            assert!(line.is_valid(&solved), "{line}");
+        }
+
+        let values = lines[2].values(&solved);
+        assert_eq!(values.iter().max().unwrap() - values.iter().min().unwrap(), 3);
+    }
+}
@@ -0,0 +1,487 @@
+use std::fmt;
+use crate::error::SudokuError;
+use crate::wavefunc::{BitSet, Cell, Sudoku};
+
+// Ordered from easiest to hardest, which is also the order in which
+// `next_step` looks for them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Technique {
+    NakedSingle,
+    HiddenSingle,
+    LockedCandidates,
+    NakedPair,
+    HiddenPair,
+    NakedTriple,
+    HiddenTriple,
+    NakedQuad,
+    HiddenQuad,
+}
+
+impl Technique {
+    pub fn name(&self) -> &'static str {
+        use Technique::*;
+        match self {
+            NakedSingle => "naked single",
+            HiddenSingle => "hidden single",
+            LockedCandidates => "locked candidates",
+            NakedPair => "naked pair",
+            HiddenPair => "hidden pair",
+            NakedTriple => "naked triple",
+            HiddenTriple => "hidden triple",
+            NakedQuad => "naked quad",
+            HiddenQuad => "hidden quad",
+        }
+    }
+
+    fn naked(size: usize) -> Technique {
+        match size {
+            2 => Technique::NakedPair,
+            3 => Technique::NakedTriple,
+            _ => Technique::NakedQuad,
+        }
+    }
+
+    fn hidden(size: usize) -> Technique {
+        match size {
+            2 => Technique::HiddenPair,
+            3 => Technique::HiddenTriple,
+            _ => Technique::HiddenQuad,
+        }
+    }
+}
+
+impl fmt::Display for Technique {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum House {
+    Row(usize),
+    Col(usize),
+    Zone(usize),
+}
+
+impl House {
+    pub fn all() -> impl Iterator<Item = House> {
+        (0..9).map(House::Row)
+            .chain((0..9).map(House::Col))
+            .chain((0..9).map(House::Zone))
+    }
+
+    pub fn cells(&self) -> [(usize, usize); 9] {
+        match *self {
+            House::Row(r) => std::array::from_fn(|i| (r, i)),
+            House::Col(c) => std::array::from_fn(|i| (i, c)),
+            House::Zone(z) => std::array::from_fn(|i| (3 * (z / 3) + i / 3, 3 * (z % 3) + i % 3)),
+        }
+    }
+}
+
+impl fmt::Display for House {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            House::Row(r) => write!(f, "row {}", r + 1),
+            House::Col(c) => write!(f, "column {}", c + 1),
+            House::Zone(z) => write!(f, "box {}", z + 1),
+        }
+    }
+}
+
+// A single deduction. `cells` are the cells that justify it: the pattern
+// itself, and for singles the target cell followed by the placed digits
+// that rule out the alternatives. The effect is either a placement or a
+// list of (row, col, value) candidate eliminations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub technique: Technique,
+    pub house: Option<House>,
+    pub cells: Vec<(usize, usize)>,
+    pub placement: Option<(usize, usize, u8)>,
+    pub eliminations: Vec<(usize, usize, u8)>,
+}
+
+impl Step {
+    pub fn apply(&self, sudoku: &Sudoku) -> Sudoku {
+        let mut sudoku = match self.placement {
+            Some((row, col, val)) => sudoku.set(row, col, val),
+            None => *sudoku,
+        };
+        for &(row, col, val) in &self.eliminations {
+            sudoku = sudoku.without(row, col, val);
+        }
+        sudoku
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.technique)?;
+        if let Some(house) = self.house {
+            write!(f, " in {house}")?;
+        }
+        if let Some((row, col, val)) = self.placement {
+            write!(f, ": r{}c{} = {val}", row + 1, col + 1)?;
+        }
+        if !self.eliminations.is_empty() {
+            let eliminations: Vec<String> = self.eliminations.iter()
+                .map(|(row, col, val)| format!("r{}c{} <> {val}", row + 1, col + 1))
+                .collect();
+            write!(f, ": {}", eliminations.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Deduction {
+    pub sudoku: Sudoku,
+    pub steps: Vec<Step>,
+}
+
+impl Deduction {
+    pub fn is_solved(&self) -> bool {
+        self.sudoku.is_solved()
+    }
+}
+
+pub fn solve(grid: [[u8; 9]; 9]) -> Result<Deduction, SudokuError> {
+    Ok(deduce(&Sudoku::from(grid)?))
+}
+
+// The easiest deduction available in `sudoku`, or `None` if the grid is
+// full or needs more than the known techniques. The step is applied to
+// `sudoku` as well, so its eliminations are kept and the next call carries
+// on from there. Entries are trusted as given, so a wrong but
+// non-conflicting entry leads to hints towards a dead end.
+pub fn hint(sudoku: &mut Sudoku) -> Option<Step> {
+    let step = next_step(sudoku)?;
+    *sudoku = step.apply(sudoku);
+    Some(step)
+}
+
+// Applies the easiest available step until the grid is solved, a
+// contradiction shows up or none of the techniques make progress.
+pub fn deduce(sudoku: &Sudoku) -> Deduction {
+    let mut sudoku = *sudoku;
+    let mut steps = Vec::new();
+    while sudoku.is_valid() {
+        match next_step(&sudoku) {
+            Some(step) => {
+                sudoku = step.apply(&sudoku);
+                steps.push(step);
+            },
+            None => break,
+        }
+    }
+    Deduction { sudoku, steps }
+}
+
+pub fn next_step(sudoku: &Sudoku) -> Option<Step> {
+    if !sudoku.is_valid() { return None; }
+    naked_single(sudoku)
+        .or_else(|| hidden_single(sudoku))
+        .or_else(|| locked_candidates(sudoku))
+        .or_else(|| (2..=4).find_map(|size| {
+            naked_subset(sudoku, size)
+                .or_else(|| hidden_subset(sudoku, size))
+        }))
+}
+
+fn candidates(sudoku: &Sudoku, (row, col): (usize, usize)) -> Option<BitSet> {
+    match sudoku.cell(row, col) {
+        Cell::Unknown(values) => Some(values),
+        _ => None,
+    }
+}
+
+fn is_placed(sudoku: &Sudoku, house: House, val: u8) -> bool {
+    house.cells().iter()
+        .any(|&(row, col)| sudoku.cell(row, col) == Cell::Known(val))
+}
+
+fn positions(sudoku: &Sudoku, cells: &[(usize, usize)], val: u8) -> Vec<(usize, usize)> {
+    cells.iter()
+        .copied()
+        .filter(|&cell| candidates(sudoku, cell).is_some_and(|values| values.contains(val)))
+        .collect()
+}
+
+fn zone_of((row, col): (usize, usize)) -> usize {
+    3 * (row / 3) + col / 3
+}
+
+// A cell sharing a house with `cell` where `val` is already placed.
+fn placed_peer(sudoku: &Sudoku, cell: (usize, usize), val: u8) -> Option<(usize, usize)> {
+    [House::Row(cell.0), House::Col(cell.1), House::Zone(zone_of(cell))].iter()
+        .flat_map(|house| house.cells())
+        .find(|&(row, col)| sudoku.cell(row, col) == Cell::Known(val))
+}
+
+fn naked_single(sudoku: &Sudoku) -> Option<Step> {
+    (0..9).flat_map(|row| (0..9).map(move |col| (row, col)))
+        .find_map(|cell| {
+            let values = candidates(sudoku, cell)?;
+            if values.size() != 1 { return None; }
+            let val = values.iter().next()?;
+            let mut cells = vec![cell];
+            for other in (1..=9).filter(|other| *other != val) {
+                if let Some(peer) = placed_peer(sudoku, cell, other) {
+                    if !cells.contains(&peer) { cells.push(peer); }
+                }
+            }
+            Some(Step {
+                technique: Technique::NakedSingle,
+                house: None,
+                cells,
+                placement: Some((cell.0, cell.1, val)),
+                eliminations: Vec::new(),
+            })
+        })
+}
+
+fn hidden_single(sudoku: &Sudoku) -> Option<Step> {
+    for house in House::all() {
+        let cells = house.cells();
+        for val in 1..=9 {
+            let positions = positions(sudoku, &cells, val);
+            if positions.len() != 1 || is_placed(sudoku, house, val) { continue; }
+            let (row, col) = positions[0];
+            let mut justification = positions;
+            for &other in &cells {
+                if candidates(sudoku, other).is_none() || other == (row, col) { continue; }
+                if let Some(peer) = placed_peer(sudoku, other, val) {
+                    if !justification.contains(&peer) { justification.push(peer); }
+                }
+            }
+            return Some(Step {
+                technique: Technique::HiddenSingle,
+                house: Some(house),
+                cells: justification,
+                placement: Some((row, col, val)),
+                eliminations: Vec::new(),
+            });
+        }
+    }
+    None
+}
+
+// Pointing: a value confined to one line within a box can be removed from
+// the rest of that line. Claiming: a value confined to one box within a
+// line can be removed from the rest of that box.
+fn locked_candidates(sudoku: &Sudoku) -> Option<Step> {
+    for house in House::all() {
+        let cells = house.cells();
+        for val in 1..=9 {
+            let locked = positions(sudoku, &cells, val);
+            if locked.len() < 2 { continue; }
+
+            let mut targets: Vec<House> = Vec::new();
+            if let House::Zone(_) = house {
+                if locked.iter().all(|cell| cell.0 == locked[0].0) {
+                    targets.push(House::Row(locked[0].0));
+                }
+                if locked.iter().all(|cell| cell.1 == locked[0].1) {
+                    targets.push(House::Col(locked[0].1));
+                }
+            } else if locked.iter().all(|&cell| zone_of(cell) == zone_of(locked[0])) {
+                targets.push(House::Zone(zone_of(locked[0])));
+            }
+
+            for target in targets {
+                let eliminations: Vec<(usize, usize, u8)> =
+                    positions(sudoku, &target.cells(), val).into_iter()
+                        .filter(|cell| !cells.contains(cell))
+                        .map(|(row, col)| (row, col, val))
+                        .collect();
+                if eliminations.is_empty() { continue; }
+                return Some(Step {
+                    technique: Technique::LockedCandidates,
+                    house: Some(house),
+                    cells: locked,
+                    placement: None,
+                    eliminations,
+                });
+            }
+        }
+    }
+    None
+}
+
+fn naked_subset(sudoku: &Sudoku, size: usize) -> Option<Step> {
+    for house in House::all() {
+        let unknown: Vec<((usize, usize), BitSet)> = house.cells().iter()
+            .filter_map(|&cell| Some((cell, candidates(sudoku, cell)?)))
+            .collect();
+        let small: Vec<((usize, usize), BitSet)> = unknown.iter()
+            .copied()
+            .filter(|(_, values)| (values.size() as usize) <= size)
+            .collect();
+
+        for subset in combinations(&small, size) {
+            let values = subset.iter()
+                .fold(BitSet::new(), |acc, (_, values)| acc.union(*values));
+            if values.size() as usize != size { continue; }
+
+            let cells: Vec<(usize, usize)> = subset.iter().map(|(cell, _)| *cell).collect();
+            let eliminations: Vec<(usize, usize, u8)> = unknown.iter()
+                .filter(|(cell, _)| !cells.contains(cell))
+                .flat_map(|&((row, col), other)| {
+                    values.iter()
+                        .filter(move |val| other.contains(*val))
+                        .map(move |val| (row, col, val))
+                })
+                .collect();
+            if eliminations.is_empty() { continue; }
+            return Some(Step {
+                technique: Technique::naked(size),
+                house: Some(house),
+                cells,
+                placement: None,
+                eliminations,
+            });
+        }
+    }
+    None
+}
+
+fn hidden_subset(sudoku: &Sudoku, size: usize) -> Option<Step> {
+    for house in House::all() {
+        let cells = house.cells();
+        let values: Vec<(u8, Vec<(usize, usize)>)> = (1..=9)
+            .filter(|val| !is_placed(sudoku, house, *val))
+            .map(|val| (val, positions(sudoku, &cells, val)))
+            .filter(|(_, positions)| (2..=size).contains(&positions.len()))
+            .collect();
+
+        for subset in combinations(&values, size) {
+            let mut positions: Vec<(usize, usize)> = subset.iter()
+                .flat_map(|(_, positions)| positions.iter().copied())
+                .collect();
+            positions.sort();
+            positions.dedup();
+            if positions.len() != size { continue; }
+
+            let kept = subset.iter()
+                .fold(BitSet::new(), |acc, (val, _)| acc.insert(*val));
+            let eliminations: Vec<(usize, usize, u8)> = positions.iter()
+                .flat_map(|&(row, col)| {
+                    candidates(sudoku, (row, col)).unwrap().iter()
+                        .filter(move |val| !kept.contains(*val))
+                        .map(move |val| (row, col, val))
+                })
+                .collect();
+            if eliminations.is_empty() { continue; }
+            return Some(Step {
+                technique: Technique::hidden(size),
+                house: Some(house),
+                cells: positions,
+                placement: None,
+                eliminations,
+            });
+        }
+    }
+    None
+}
+
+fn combinations<T: Clone>(items: &[T], size: usize) -> Vec<Vec<T>> {
+    if size == 0 { return vec![Vec::new()]; }
+    if items.len() < size { return Vec::new(); }
+    let mut result: Vec<Vec<T>> = combinations(&items[1..], size - 1).into_iter()
+        .map(|mut rest| { rest.insert(0, items[0].clone()); rest })
+        .collect();
+    result.extend(combinations(&items[1..], size));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::EASY;
+
+    #[test]
+    fn solves_easy_puzzle_with_singles() {
+        let deduction = solve(EASY).unwrap();
+        assert!(deduction.is_solved());
+        assert_eq!(
+            deduction.sudoku.grid(),
+            Sudoku::from(EASY).unwrap().solved().unwrap().grid()
+        );
+        assert!(deduction.steps.iter()
+            .all(|step| step.technique <= Technique::HiddenSingle));
+    }
+
+    #[test]
+    fn naked_pair_eliminates_from_house() {
+        let mut sudoku = Sudoku::empty();
+        for val in 3..=9 {
+            sudoku = sudoku.without(0, 0, val).without(0, 1, val);
+        }
+        let step = naked_subset(&sudoku, 2).unwrap();
+        assert_eq!(step.technique, Technique::NakedPair);
+        assert_eq!(step.house, Some(House::Row(0)));
+        assert_eq!(step.cells, vec![(0, 0), (0, 1)]);
+        assert!(step.eliminations.contains(&(0, 8, 1)));
+        assert!(step.eliminations.iter().all(|&(row, _, val)| row == 0 && val <= 2));
+        assert!(step.to_string().starts_with("naked pair in row 1: r1c3 <> 1, r1c3 <> 2, r1c4 <> 1"));
+    }
+
+    #[test]
+    fn hint_is_justified_by_placed_digits() {
+        let mut grid = [[0u8; 9]; 9];
+        grid[0] = [1, 2, 3, 4, 5, 6, 7, 8, 0];
+        let step = hint(&mut Sudoku::from(grid).unwrap()).unwrap();
+        assert_eq!(step.technique, Technique::NakedSingle);
+        assert_eq!(step.placement, Some((0, 8, 9)));
+        assert_eq!(step.cells.len(), 9);
+        assert_eq!(step.cells[0], (0, 8));
+
+        let mut grid = [[0u8; 9]; 9];
+        grid[1][4] = 5;
+        grid[2][7] = 5;
+        grid[0][0] = 1;
+        grid[0][1] = 2;
+        let step = hint(&mut Sudoku::from(grid).unwrap()).unwrap();
+        assert_eq!(step.technique, Technique::HiddenSingle);
+        assert_eq!(step.house, Some(House::Row(0)));
+        assert_eq!(step.placement, Some((0, 2, 5)));
+        assert_eq!(step.cells, vec![(0, 2), (1, 4), (2, 7)]);
+        assert_eq!(step.to_string(), "hidden single in row 1: r1c3 = 5");
+    }
+
+    #[test]
+    fn repeated_hints_get_past_locked_candidates() {
+        let grid = [
+            [8, 0, 0,  0, 9, 0,  0, 0, 1],
+            [0, 0, 0,  3, 0, 0,  2, 8, 0],
+            [0, 0, 2,  1, 0, 0,  0, 0, 0],
+
+            [0, 1, 0,  8, 0, 0,  0, 0, 7],
+            [3, 0, 4,  0, 0, 0,  0, 1, 0],
+            [0, 0, 0,  5, 3, 0,  0, 0, 4],
+
+            [2, 6, 0,  9, 0, 0,  0, 0, 0],
+            [1, 0, 8,  7, 0, 5,  0, 0, 0],
+            [0, 0, 5,  0, 0, 0,  0, 6, 9],
+        ];
+        let mut sudoku = Sudoku::from(grid).unwrap();
+        let mut techniques = Vec::new();
+        while !sudoku.is_solved() {
+            let before = sudoku;
+            let step = hint(&mut sudoku).expect("the techniques solve this puzzle");
+            assert_ne!(sudoku, before);
+            techniques.push(step.technique);
+        }
+        assert!(techniques.contains(&Technique::LockedCandidates));
+        assert_eq!(sudoku.grid(), Sudoku::from(grid).unwrap().solved().unwrap().grid());
+    }
+
+    #[test]
+    fn steps_replay_to_the_same_state() {
+        let deduction = solve(EASY).unwrap();
+        let replayed = deduction.steps.iter()
+            .fold(Sudoku::from(EASY).unwrap(), |sudoku, step| step.apply(&sudoku));
+        assert_eq!(replayed.grid(), deduction.sudoku.grid());
+    }
+}
@@ -2,6 +2,9 @@
 
 use colored::Colorize;
 use sudoku::{backtrack, sets, bits, wavefunc};
+use sudoku::solver::{self, Solver};
+use sudoku::grade;
+use sudoku::parse;
 use std::time::Instant;
 use std::env;
 
@@ -49,21 +52,7 @@ fn main() {
     
     let args: Vec<String> = env::args().collect();
     let test_grids = sudoku_from_file(&args[1]);
-    performance_test(&test_grids, vec![
-        (
-            "backtracking",
-            Box::new(|grid| { assert!(backtrack::Sudoku::from(*grid).unwrap().solve()); }),
-        ), (
-            "backtracking + sets",
-            Box::new(|grid| { assert!(sets::Sudoku::from(*grid).unwrap().solve()); }),
-        ), (
-            "backtracking + bitsets",
-            Box::new(|grid| { assert!(bits::Sudoku::from(*grid).unwrap().solve()); }),
-        ), (
-            "wavefunc",
-            Box::new(|grid| { wavefunc::Sudoku::from(*grid).solved().unwrap(); }),
-        )
-    ]);
+    performance_test(&test_grids, &solver::all());
     // if args.len() >= 4 {
     //     performance_single_grid(
     //         test_grids[args[2].parse::<usize>().expect("Could not parse grid index")],
@@ -83,45 +72,37 @@ fn measure(f: impl FnOnce()) -> u128 {
 fn sudoku_from_file(filename: &String) -> Vec<[[u8; 9]; 9]> {
     let contents = std::fs::read_to_string(filename)
         .expect("could not read file");
-    let contents: Vec<&str> =
-        contents.split("\n\n")
-        .collect();
-    let mut grids: Vec<[[u8; 9]; 9]> = Vec::new();
-    contents.into_iter().for_each(|grid| {
-        let mut tmp = [[0u8; 9]; 9];
-        grid.split('\n').enumerate().for_each(|(row, line)| {
-            line.split(' ').enumerate().for_each(|(col, elem)| {
-                tmp[row][col] = elem.parse().expect("failed to parse");
-            });
-        });
-        grids.push(tmp);
-    });
-    grids
+    parse::grids(&contents).into_iter()
+        .filter_map(|grid| grid.map_err(|err| eprintln!("{filename}: skipping a puzzle at {err}")).ok())
+        .collect()
 }
 
-fn performance_test(test_grids: &Vec<[[u8; 9]; 9]>, solvers: Vec<(&str, Box<dyn Fn(&[[u8; 9]; 9])>)>) {
+fn performance_test(test_grids: &[[[u8; 9]; 9]], solvers: &[Box<dyn Solver>]) {
     let mut deltasums = vec![0u128; solvers.len()];
 
     for grid in test_grids {
-        println!("\n\nNow solving:");
+        match grade::grade(*grid) {
+            Ok(grade) => println!("\n\nNow solving ({}, score {}):", grade.difficulty, grade.score),
+            Err(err) => println!("\n\nNow solving ({err}):"),
+        }
         print_grid(grid);
         println!();
-        for (i, (label, solver)) in solvers.iter().enumerate() {
+        for (i, solver) in solvers.iter().enumerate() {
             let mut total_delta = 0;
             let n = 1000;
             for _ in 0..n {
-                total_delta += measure(|| { solver(grid); });
+                total_delta += measure(|| { assert!(solver.solve(grid).is_solved()); });
             }
             let average = total_delta / n;
             deltasums[i] += average;
-            println!("{label}: {:.4}", average);
+            println!("{}: {:.4}", solver.name(), average);
         }
     }
 
     println!("{}", "\n\nThe total sum for all solvers were as follows:".green());
-    for (sum, (label, _))
+    for (sum, solver)
     in deltasums.iter().zip(solvers) {
-        println!("{label}: {:.4}", sum);
+        println!("{}: {:.4}", solver.name(), sum);
     }
 }
 
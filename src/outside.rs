@@ -0,0 +1,570 @@
+use std::fmt;
+use crate::error::SudokuError;
+use crate::rules::{self, Constraint};
+use crate::wavefunc::{BitSet, Sudoku};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl Side {
+    pub fn name(&self) -> &'static str {
+        use Side::*;
+        match self {
+            Top => "top",
+            Bottom => "bottom",
+            Left => "left",
+            Right => "right",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Side> {
+        use Side::*;
+        [Top, Bottom, Left, Right].into_iter()
+            .find(|side| side.name() == name)
+    }
+
+    // One step away from this side, into the grid.
+    fn inward(&self) -> (isize, isize) {
+        use Side::*;
+        match self {
+            Top => (1, 0),
+            Bottom => (-1, 0),
+            Left => (0, 1),
+            Right => (0, -1),
+        }
+    }
+
+    // The cell at `index` along this side, next to it.
+    fn edge(&self, index: usize) -> (usize, usize) {
+        use Side::*;
+        match self {
+            Top => (0, index),
+            Bottom => (8, index),
+            Left => (index, 0),
+            Right => (index, 8),
+        }
+    }
+
+    fn is_horizontal(&self) -> bool {
+        matches!(self, Side::Top | Side::Bottom)
+    }
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutsideKind {
+    // the digits between the 1 and the 9 of the row or column add up to the clue
+    Sandwich,
+    // reading the digits as heights, this many can be seen from the clue
+    Skyscraper,
+    // the digits on the diagonal add up to the clue and may repeat; the
+    // diagonal heads away from the clue's side towards `towards`
+    LittleKiller { towards: Side },
+}
+
+impl OutsideKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutsideKind::Sandwich => "sandwich",
+            OutsideKind::Skyscraper => "skyscraper",
+            OutsideKind::LittleKiller { .. } => "little-killer",
+        }
+    }
+}
+
+// A clue written outside the grid, next to the cell at `index` along `side`,
+// about the cells read from there inwards.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutsideClue {
+    kind: OutsideKind,
+    side: Side,
+    index: usize,
+    value: u8,
+    cells: Vec<(usize, usize)>,
+    // for sandwiches, every set of digits from 2 to 8 that adds up to the
+    // clue, whatever its size
+    combinations: Vec<BitSet>,
+}
+
+impl OutsideClue {
+    pub fn sandwich(side: Side, index: usize, sum: u8) -> Result<OutsideClue, SudokuError> {
+        let combinations: Vec<BitSet> = (0u32..1 << 7)
+            .map(|mask| (2..=8u8)
+                .filter(|n| mask & 1 << (n - 2) != 0)
+                .fold(BitSet::new(), |set, n| set.insert(n)))
+            .filter(|set| set.iter().map(u32::from).sum::<u32>() == sum as u32)
+            .collect();
+        if combinations.is_empty() {
+            return Err(SudokuError::InvalidConstraint { reason: "no distinct digits from 2 to 8 add up to the sandwich" });
+        }
+        Ok(OutsideClue { combinations, ..OutsideClue::new(OutsideKind::Sandwich, side, index, sum)? })
+    }
+
+    pub fn skyscraper(side: Side, index: usize, count: u8) -> Result<OutsideClue, SudokuError> {
+        if !(1..=9).contains(&count) {
+            return Err(SudokuError::InvalidConstraint { reason: "between 1 and 9 skyscrapers can be seen" });
+        }
+        OutsideClue::new(OutsideKind::Skyscraper, side, index, count)
+    }
+
+    pub fn little_killer(side: Side, index: usize, towards: Side, sum: u8) -> Result<OutsideClue, SudokuError> {
+        if side.is_horizontal() == towards.is_horizontal() {
+            return Err(SudokuError::InvalidConstraint { reason: "a little killer must head towards a neighbouring side" });
+        }
+        let clue = OutsideClue::new(OutsideKind::LittleKiller { towards }, side, index, sum)?;
+        let len = clue.cells.len();
+        if len < 2 {
+            return Err(SudokuError::InvalidConstraint { reason: "a little killer needs a diagonal of at least two cells" });
+        }
+        if (sum as usize) < len || sum as usize > 9 * len {
+            return Err(SudokuError::InvalidConstraint { reason: "no digits on the diagonal add up to the little killer" });
+        }
+        Ok(clue)
+    }
+
+    fn new(kind: OutsideKind, side: Side, index: usize, value: u8) -> Result<OutsideClue, SudokuError> {
+        let (row, col) = side.edge(index);
+        if index >= 9 {
+            return Err(SudokuError::IndexOutOfRange { row, col, size: 9 });
+        }
+        let (dr, dc) = direction(kind, side);
+        let cells = (0..9)
+            .map(|i| (row as isize + i * dr, col as isize + i * dc))
+            .take_while(|&(r, c)| (0..9).contains(&r) && (0..9).contains(&c))
+            .map(|(r, c)| (r as usize, c as usize))
+            .collect();
+        Ok(OutsideClue { kind, side, index, value, cells, combinations: Vec::new() })
+    }
+
+    // Reads one clue per row of text: its kind, side, the row or column it
+    // stands next to counting from 1, and its value, as in `sandwich top 3 15`
+    // or `skyscraper left 2 4`. Little killers name the side they head
+    // towards before the value: `little-killer top 1 right 45`. Comments
+    // start with `#`, as in the other clue lists.
+    pub fn parse(text: &str) -> Result<Vec<OutsideClue>, SudokuError> {
+        let mut clues = Vec::new();
+        for (_, row) in rules::clue_rows(text) {
+            let words: Vec<&str> = row.split_whitespace().collect();
+            let len = match words[0] {
+                "sandwich" | "skyscraper" => 4,
+                "little-killer" => 5,
+                _ => return Err(SudokuError::InvalidConstraint { reason: "unknown kind of outside clue" }),
+            };
+            if words.len() < len {
+                return Err(SudokuError::InvalidConstraint { reason: "too few words for an outside clue" });
+            }
+            if words.len() > len {
+                return Err(SudokuError::InvalidConstraint { reason: "too many words for an outside clue" });
+            }
+            let side = |word: &str| Side::from_name(word)
+                .ok_or(SudokuError::InvalidConstraint { reason: "sides are top, bottom, left or right" });
+            let number = |word: &str| word.parse::<u8>()
+                .map_err(|_| SudokuError::InvalidConstraint { reason: "expected a number" });
+            let index = number(words[2])?.checked_sub(1)
+                .ok_or(SudokuError::InvalidConstraint { reason: "rows and columns count from 1" })? as usize;
+            let clue = match words[0] {
+                "sandwich" => OutsideClue::sandwich(side(words[1])?, index, number(words[3])?)?,
+                "skyscraper" => OutsideClue::skyscraper(side(words[1])?, index, number(words[3])?)?,
+                _ => OutsideClue::little_killer(side(words[1])?, index, side(words[3])?, number(words[4])?)?,
+            };
+            clues.push(clue);
+        }
+        Ok(clues)
+    }
+
+    pub fn kind(&self) -> OutsideKind {
+        self.kind
+    }
+
+    pub fn side(&self) -> Side {
+        self.side
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn value(&self) -> u8 {
+        self.value
+    }
+
+    pub fn cells(&self) -> &[(usize, usize)] {
+        &self.cells
+    }
+
+    // The candidates of each cell that some way of meeting the clue uses,
+    // or no candidates at all if there is none.
+    fn allowed(&self, candidates: &[BitSet]) -> Vec<BitSet> {
+        if candidates.iter().any(BitSet::is_empty) { return vec![BitSet::new(); candidates.len()]; }
+        match self.kind {
+            OutsideKind::Sandwich => sandwich_allowed(candidates, &self.combinations),
+            OutsideKind::Skyscraper => skyscraper_allowed(candidates, self.value),
+            OutsideKind::LittleKiller { .. } => little_killer_allowed(candidates, self.value),
+        }
+    }
+
+    fn candidates(&self, sudoku: &Sudoku) -> Vec<BitSet> {
+        self.cells.iter()
+            .map(|&(r, c)| sudoku.cell(r, c).possible_values())
+            .collect()
+    }
+
+    // How the clue reads in the border: its value, and an arrow along the
+    // diagonal for little killers.
+    fn label(&self) -> String {
+        match self.kind {
+            OutsideKind::LittleKiller { .. } => {
+                let arrow = match direction(self.kind, self.side) {
+                    (1, 1) => '↘',
+                    (1, _) => '↙',
+                    (_, 1) => '↗',
+                    _ => '↖',
+                };
+                format!("{}{arrow}", self.value)
+            },
+            _ => self.value.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for OutsideClue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.kind.name(), self.side, self.index + 1)?;
+        if let OutsideKind::LittleKiller { towards } = self.kind {
+            write!(f, " {towards}")?;
+        }
+        write!(f, " {}", self.value)
+    }
+}
+
+// The step from one cell of a clue to the next.
+fn direction(kind: OutsideKind, side: Side) -> (isize, isize) {
+    let (dr, dc) = side.inward();
+    match kind {
+        OutsideKind::LittleKiller { towards } => (dr - towards.inward().0, dc - towards.inward().1),
+        _ => (dr, dc),
+    }
+}
+
+// Tries every pair of cells for the 1 and the 9, and every set of digits
+// that fits between them.
+fn sandwich_allowed(candidates: &[BitSet], combinations: &[BitSet]) -> Vec<BitSet> {
+    let mut allowed = vec![BitSet::new(); candidates.len()];
+    for one in (0..candidates.len()).filter(|&i| candidates[i].contains(1)) {
+        for nine in (0..candidates.len()).filter(|&i| candidates[i].contains(9) && i != one) {
+            let (first, last) = (one.min(nine), one.max(nine));
+            let between = &candidates[first + 1..last];
+            for &digits in combinations.iter().filter(|digits| digits.size() as usize == between.len()) {
+                if !rules::fits(between, digits) { continue; }
+                let crusts = digits.insert(1).insert(9);
+                for (i, cell) in candidates.iter().enumerate() {
+                    let keep = if i == one { BitSet::from_single(1) }
+                        else if i == nine { BitSet::from_single(9) }
+                        else if first < i && i < last { cell.intersection(digits) }
+                        else { cell.iter().filter(|&n| !crusts.contains(n)).fold(BitSet::new(), |set, n| set.insert(n)) };
+                    allowed[i] = allowed[i].union(keep);
+                }
+            }
+        }
+    }
+    allowed
+}
+
+// Walks the cells from the clue keeping track of the tallest building so far
+// and how many have been seen, which is all that decides the count. Digits
+// aren't kept apart here, the row or column does that, but the 9 has to turn
+// up somewhere.
+fn skyscraper_allowed(candidates: &[BitSet], count: u8) -> Vec<BitSet> {
+    let len = candidates.len();
+    let step = |(tallest, seen): (u8, u8), n: u8| if n > tallest { (n, seen + 1) } else { (tallest, seen) };
+
+    // reachable[i][tallest][seen] before cell i
+    let mut reachable = vec![[[false; 10]; 10]; len + 1];
+    reachable[0][0][0] = true;
+    for i in 0..len {
+        for tallest in 0..10 {
+            for seen in 0..10 {
+                if !reachable[i][tallest][seen] { continue; }
+                for n in candidates[i].iter() {
+                    let (tallest, seen) = step((tallest as u8, seen as u8), n);
+                    reachable[i + 1][tallest as usize][seen as usize] = true;
+                }
+            }
+        }
+    }
+    // finishing[i][tallest][seen] when the rest can still make the count
+    let mut finishing = vec![[[false; 10]; 10]; len + 1];
+    finishing[len][9][count as usize] = true;
+    let mut allowed = vec![BitSet::new(); len];
+    for i in (0..len).rev() {
+        for tallest in 0..10 {
+            for seen in 0..9 {
+                for n in candidates[i].iter() {
+                    let next = step((tallest as u8, seen as u8), n);
+                    if !finishing[i + 1][next.0 as usize][next.1 as usize] { continue; }
+                    finishing[i][tallest][seen] = true;
+                    if reachable[i][tallest][seen] { allowed[i] = allowed[i].insert(n); }
+                }
+            }
+        }
+    }
+    allowed
+}
+
+// Keeps the digits that leave the rest of the diagonal a reachable sum.
+fn little_killer_allowed(candidates: &[BitSet], sum: u8) -> Vec<BitSet> {
+    let lowest: u32 = candidates.iter().map(|cell| cell.iter().min().unwrap_or(0) as u32).sum();
+    let highest: u32 = candidates.iter().map(|cell| cell.iter().max().unwrap_or(0) as u32).sum();
+    candidates.iter()
+        .map(|cell| {
+            let (low, high) = (cell.iter().min().unwrap_or(0) as u32, cell.iter().max().unwrap_or(0) as u32);
+            cell.iter()
+                .filter(|&n| lowest - low + n as u32 <= sum as u32 && sum as u32 <= highest - high + n as u32)
+                .fold(BitSet::new(), |set, n| set.insert(n))
+        })
+        .collect()
+}
+
+impl Constraint for OutsideClue {
+    // Only checks finished lines; pruning empties a cell as soon as a
+    // partly filled one can't be finished.
+    fn is_valid(&self, sudoku: &Sudoku) -> bool {
+        let grid = sudoku.grid();
+        let values: Vec<BitSet> = self.cells.iter()
+            .map(|&(r, c)| grid[r][c])
+            .map_while(|n| (n != 0).then(|| BitSet::from_single(n)))
+            .collect();
+        values.len() < self.cells.len() || self.allowed(&values).iter().all(|cell| !cell.is_empty())
+    }
+
+    fn prune(&self, sudoku: &Sudoku) -> Sudoku {
+        rules::restrict(sudoku, &self.cells, &self.allowed(&self.candidates(sudoku)))
+    }
+}
+
+// A grid with its outside clues written around it.
+pub struct Bordered<'a> {
+    grid: &'a [[u8; 9]; 9],
+    clues: &'a [OutsideClue],
+}
+
+impl<'a> Bordered<'a> {
+    pub fn new(grid: &'a [[u8; 9]; 9], clues: &'a [OutsideClue]) -> Bordered<'a> {
+        Bordered { grid, clues }
+    }
+
+    // The clues next to `index` along `side`, split by slashes if there are
+    // several.
+    fn label(&self, side: Side, index: usize) -> String {
+        self.clues.iter()
+            .filter(|clue| clue.side == side && clue.index == index)
+            .map(OutsideClue::label)
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+impl fmt::Display for Bordered<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Side::*;
+        let labels = |side| (0..9).map(|i| self.label(side, i)).collect::<Vec<_>>();
+        let (top, bottom, left, right) = (labels(Top), labels(Bottom), labels(Left), labels(Right));
+        let width = [&top, &bottom, &left, &right].iter()
+            .flat_map(|labels| labels.iter())
+            .map(|label| label.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(1);
+
+        let row = |cells: &[String]| {
+            let mut s = String::new();
+            for (col, cell) in cells.iter().enumerate() {
+                s.push_str(&format!("{cell:>width$} "));
+                if col % 3 == 2 && col < 8 { s.push(' '); }
+            }
+            s
+        };
+        let margin = " ".repeat(width + 2);
+        writeln!(f, "{margin}{}", row(&top).trim_end())?;
+        writeln!(f)?;
+        for r in 0..9 {
+            let digits: Vec<String> = self.grid[r].iter()
+                .map(|&n| if n == 0 { ".".to_string() } else { n.to_string() })
+                .collect();
+            let digits = row(&digits);
+            writeln!(f, "{:>width$}  {digits} {}", left[r], right[r])?;
+            if r % 3 == 2 { writeln!(f)?; }
+        }
+        write!(f, "{margin}{}", row(&bottom).trim_end())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::Rules;
+    use crate::test_util::{self, SOLUTION};
+
+    #[test]
+    fn parses_outside_clues() {
+        let clues = OutsideClue::parse("
+            sandwich top 3 15   # column 3
+            Skyscraper LEFT 2 4
+            little-killer bottom 1 right 45
+        ").unwrap();
+        assert_eq!(clues[0].kind(), OutsideKind::Sandwich);
+        assert_eq!(clues[0].cells()[..2], [(0, 2), (1, 2)]);
+        assert_eq!(clues[1].cells()[..2], [(1, 0), (1, 1)]);
+        assert_eq!(clues[2].cells(), (0..9).map(|i| (8 - i, i)).collect::<Vec<_>>());
+        assert_eq!(clues[2].to_string(), "little-killer bottom 1 right 45");
+
+        assert!(OutsideClue::parse("sandwich top 3 1").is_err());
+        assert!(OutsideClue::parse("skyscraper top 10 3").is_err());
+        assert!(OutsideClue::parse("little-killer top 1 bottom 5").is_err());
+        assert!(OutsideClue::parse("little-killer top 9 right 10").is_err());
+
+        let reason = |text| match OutsideClue::parse(text) {
+            Err(SudokuError::InvalidConstraint { reason }) => reason,
+            other => panic!("{other:?}"),
+        };
+        assert_eq!(reason("arrow top x 5"), "unknown kind of outside clue");
+        assert_eq!(reason("sandwich top 3"), "too few words for an outside clue");
+        assert_eq!(reason("skyscraper top 3 4 5"), "too many words for an outside clue");
+        assert_eq!(reason("little-killer top 3 right"), "too few words for an outside clue");
+        assert_eq!(reason("sandwich top three 5"), "expected a number");
+        assert_eq!(reason("little-killer top 9 right 9"), "a little killer needs a diagonal of at least two cells");
+        assert_eq!(reason("little-killer left 9 bottom 5"), "a little killer needs a diagonal of at least two cells");
+    }
+
+    #[test]
+    fn sandwiches_place_the_one_and_the_nine() {
+        let empty = [[0u8; 9]; 9];
+        // 35 is every digit from 2 to 8, so the 1 and the 9 are at the ends
+        let sudoku = test_util::pruned(OutsideClue::parse("sandwich left 1 35").unwrap(), empty);
+        assert_eq!(sudoku.cell(0, 0).possible_values(), BitSet::new().insert(1).insert(9));
+        assert_eq!(sudoku.cell(0, 8).possible_values(), BitSet::new().insert(1).insert(9));
+        assert_eq!(sudoku.cell(0, 4).possible_values().size(), 7);
+
+        // 0 puts them side by side
+        let mut grid = empty;
+        grid[0][0] = 1;
+        let sudoku = test_util::pruned(OutsideClue::parse("sandwich top 1 0").unwrap(), grid);
+        assert_eq!(sudoku.cell(1, 0).possible_values(), BitSet::from_single(9));
+
+        assert!(OutsideClue::sandwich(Side::Top, 0, 36).is_err());
+        assert!(OutsideClue::sandwich(Side::Top, 0, 1).is_err());
+    }
+
+    #[test]
+    fn skyscrapers_count_from_their_side() {
+        let empty = [[0u8; 9]; 9];
+        let sudoku = test_util::pruned(OutsideClue::parse("skyscraper top 5 1").unwrap(), empty);
+        assert_eq!(sudoku.cell(0, 4).possible_values(), BitSet::from_single(9));
+
+        // all nine in view means they rise one by one
+        let sudoku = test_util::pruned(OutsideClue::parse("skyscraper right 3 9").unwrap(), empty);
+        assert_eq!(sudoku.cell(2, 8).possible_values(), BitSet::from_single(1));
+        assert_eq!(sudoku.cell(2, 0).possible_values(), BitSet::from_single(9));
+
+        let sudoku = test_util::pruned(OutsideClue::parse("skyscraper bottom 1 3").unwrap(), empty);
+        assert!(!sudoku.cell(8, 0).possible_values().contains(9));
+        assert!(!sudoku.cell(8, 0).possible_values().contains(8));
+
+        // the first row of the solution shows 5, 6, 7, 8 and 9 from the left
+        let solution = Sudoku::from(SOLUTION).unwrap();
+        assert!(OutsideClue::skyscraper(Side::Left, 0, 5).unwrap().is_valid(&solution));
+        assert!(!OutsideClue::skyscraper(Side::Left, 0, 4).unwrap().is_valid(&solution));
+        assert!(OutsideClue::skyscraper(Side::Left, 0, 0).is_err());
+    }
+
+    #[test]
+    fn little_killers_add_up_their_diagonal() {
+        let empty = [[0u8; 9]; 9];
+        let sudoku = test_util::pruned(OutsideClue::parse("little-killer right 7 bottom 4").unwrap(), empty);
+        assert_eq!(sudoku.cell(6, 8).possible_values().iter().max(), Some(2));
+
+        // the shortest diagonal has two cells, so 18 means two nines
+        let clue = OutsideClue::little_killer(Side::Right, 7, Side::Bottom, 18).unwrap();
+        assert_eq!(clue.cells(), [(7, 8), (8, 7)]);
+        let sudoku = test_util::pruned([clue], empty);
+        assert_eq!(sudoku.cell(8, 7).possible_values(), BitSet::from_single(9));
+
+        assert!(OutsideClue::little_killer(Side::Right, 7, Side::Bottom, 19).is_err());
+        assert!(OutsideClue::little_killer(Side::Right, 7, Side::Bottom, 1).is_err());
+        assert!(OutsideClue::little_killer(Side::Top, 0, Side::Bottom, 45).is_err());
+    }
+
+    #[test]
+    fn solves_with_outside_clues() {
+        let text = "
+            sandwich left 4 13
+            sandwich left 6 3
+            sandwich left 7 6
+            sandwich left 9 7
+            sandwich top 1 19
+            sandwich top 2 7
+            sandwich top 3 9
+            sandwich top 4 18
+            sandwich top 5 20
+            sandwich top 6 14
+            sandwich top 8 12
+            sandwich top 9 15
+            skyscraper left 1 5
+            skyscraper right 4 5
+            skyscraper top 8 4
+            skyscraper bottom 7 4
+            little-killer top 1 right 50
+            little-killer top 9 left 38
+        ";
+        let clues = OutsideClue::parse(text).unwrap();
+        let mut rules = Rules::standard();
+        for clue in &clues {
+            rules.add_outside_clue(clue.clone());
+        }
+        // a few digits to keep the search short
+        let mut grid = [[0u8; 9]; 9];
+        for (r, c) in [(0, 0), (1, 2), (2, 4), (5, 1), (6, 3), (7, 5)] {
+            grid[r][c] = SOLUTION[r][c];
+        }
+        let solved = Sudoku::from_rules(grid, &rules).unwrap()
+            .solved_with(&rules)
+            .unwrap();
+        assert!(crate::error::validate(&solved.grid()).is_ok());
+        for clue in &clues {
+            assert!(clue.is_valid(&solved), "{clue}");
+        }
+
+        let expected = Sudoku::from(SOLUTION).unwrap();
+        assert!(clues.iter().all(|clue| clue.is_valid(&expected)));
+    }
+
+    #[test]
+    fn writes_clues_around_the_grid() {
+        let clues = OutsideClue::parse("
+            sandwich top 1 19
+            skyscraper top 1 4
+            little-killer top 9 left 45
+            skyscraper right 9 1
+            little-killer left 1 bottom 50
+        ").unwrap();
+        let mut grid = SOLUTION;
+        grid[4][4] = 0;
+        let text = Bordered::new(&grid, &clues).to_string();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0].trim_end(), "      19/4                                       45↙");
+        assert_eq!(lines[2].trim_end(), " 50↘     5    3    4     6    7    8     9    1    2");
+        assert_eq!(lines[7].trim_end(), "         4    2    6     8    .    3     7    9    1");
+        assert_eq!(lines[12].trim_end(), "         3    4    5     2    8    6     1    7    9  1");
+        assert_eq!(lines.len(), 15);
+    }
+}
@@ -0,0 +1,190 @@
+use std::{error, fmt};
+use crate::format;
+
+// Where and why a puzzle couldn't be read. Lines and columns count from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.reason)
+    }
+}
+
+impl error::Error for ParseError {}
+
+type Parsed = Result<[[u8; 9]; 9], ParseError>;
+
+// Reads every puzzle of a file, each one on its own, so that a bad puzzle
+// doesn't keep the others from being read. Puzzles are separated by blank
+// lines and are either nine rows of nine cells, with or without spaces
+// between them, or one puzzle per line in the 81-character format. Cells
+// are digits, with `.` or `0` for blanks. Anything after a `#` is a comment;
+// `|` between cells and lines made of `-`, `+` and `|` are ignored, and so
+// are CRLF line endings and trailing whitespace.
+pub fn grids(text: &str) -> Vec<Result<[[u8; 9]; 9], ParseError>> {
+    numbered_grids(text).into_iter()
+        .map(|(_, grid)| grid)
+        .collect()
+}
+
+// Reads a file holding a single puzzle.
+pub fn grid(text: &str) -> Result<[[u8; 9]; 9], ParseError> {
+    let mut grids = numbered_grids(text).into_iter();
+    let (_, grid) = grids.next()
+        .unwrap_or((1, Err(ParseError { line: 1, column: 1, reason: "there is no puzzle" })));
+    match grids.next() {
+        Some((line, _)) => Err(ParseError { line, column: 1, reason: "expected a single puzzle" }),
+        None => grid,
+    }
+}
+
+// `grids`, each with the line it starts on.
+fn numbered_grids(text: &str) -> Vec<(usize, Parsed)> {
+    let mut puzzles = Vec::new();
+    // the content of each line of the current puzzle, by line number
+    let mut block: Vec<(usize, &str)> = Vec::new();
+    let lines = text.lines().chain(std::iter::once(""));
+    for (i, line) in lines.enumerate() {
+        let content = line.split('#').next().unwrap_or("").trim_end();
+        if content.trim().is_empty() {
+            // comments don't split puzzles, blank lines do
+            if line.trim().is_empty() && !block.is_empty() {
+                puzzles.extend(block_grids(&block, i + 1));
+                block.clear();
+            }
+            continue;
+        }
+        if content.chars().all(|ch| ch.is_whitespace() || "-+|".contains(ch)) { continue; }
+        block.push((i + 1, content));
+    }
+    puzzles
+}
+
+// The puzzles of lines not split by a blank line, which is at `end`.
+fn block_grids(block: &[(usize, &str)], end: usize) -> Vec<(usize, Parsed)> {
+    let cells = |line: &str| line.chars().filter(|&ch| ch.is_ascii_digit() || ch == '.').count();
+    // one puzzle per line if any line holds a whole puzzle, or if a lone line
+    // holds more than a row; otherwise rows of a grid, even with a typo
+    let one_per_line = block.iter().any(|&(_, line)| cells(line) >= 81)
+        || (block.len() == 1 && cells(block[0].1) > 9);
+    if one_per_line {
+        return block.iter()
+            .map(|&(number, line)| {
+                let content = line.trim_start();
+                let indent = line.chars().count() - content.chars().count();
+                let grid = format::from_numbered_line(content, number)
+                    .map_err(|err| ParseError { column: err.column + indent, ..err });
+                (number, grid)
+            })
+            .collect();
+    }
+
+    vec![(block[0].0, rows(block, end))]
+}
+
+// Reads nine rows of nine cells from the content of each line, by line
+// number, with the line after the last at `end`.
+pub(crate) fn rows(block: &[(usize, &str)], end: usize) -> Parsed {
+    let mut grid = [[0u8; 9]; 9];
+    for (row, &(number, line)) in block.iter().enumerate() {
+        let error = |column, reason| Err(ParseError { line: number, column, reason });
+        if row == 9 { return error(1, "a grid has only 9 rows"); }
+        let mut col = 0;
+        for (x, ch) in line.chars().enumerate() {
+            if ch.is_whitespace() || ch == '|' { continue; }
+            if !(ch.is_ascii_digit() || ch == '.') { return error(x + 1, "expected a digit, '.' or a separator"); }
+            if col == 9 { return error(x + 1, "a row has only 9 cells"); }
+            grid[row][col] = if ch == '.' { 0 } else { ch as u8 - b'0' };
+            col += 1;
+        }
+        if col < 9 { return error(line.chars().count() + 1, "a row needs 9 cells"); }
+    }
+    if block.len() < 9 {
+        return Err(ParseError { line: end, column: 1, reason: "a grid needs 9 rows" });
+    }
+    Ok(grid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::EASY;
+
+    // EASY with a space between cells.
+    fn spaced() -> String {
+        EASY.iter()
+            .map(|row| row.map(|n| n.to_string()).join(" "))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    // EASY drawn in boxes, with a comment above and one beside a row.
+    fn boxed() -> String {
+        let mut text = String::from("# the same puzzle, drawn\n");
+        for (r, row) in EASY.iter().enumerate() {
+            if r == 3 || r == 6 { text.push_str("----+-----+----\n"); }
+            let boxes: Vec<String> = row.chunks(3)
+                .map(|cells| cells.iter().map(|&n| if n == 0 { '.' } else { (b'0' + n) as char }).collect())
+                .collect();
+            text.push_str(&boxes.join(" | "));
+            if r == 4 { text.push_str("  # middle"); }
+            text.push('\n');
+        }
+        text
+    }
+
+    #[test]
+    fn reads_every_layout() {
+        let (spaced, boxed) = (spaced(), boxed());
+        let expected = grid(&spaced).unwrap();
+        assert_eq!(expected, EASY);
+        assert_eq!(grid(&boxed), Ok(expected));
+        assert_eq!(grid(&boxed.replace('\n', "\r\n")), Ok(expected));
+
+        let line = format::to_line(&expected);
+        let text = format!("{spaced}\n\n{boxed}\n\n# a collection\n{line}\n  {line} # indented\n");
+        let grids: Vec<_> = grids(&text).into_iter().collect::<Result<_, _>>().unwrap();
+        assert_eq!(grids, vec![expected; 4]);
+    }
+
+    #[test]
+    fn points_at_the_mistake() {
+        let (spaced, boxed) = (spaced(), boxed());
+        assert_eq!(
+            grid(&spaced.replacen("7", "x", 1)),
+            Err(ParseError { line: 1, column: 9, reason: "expected a digit, '.' or a separator" })
+        );
+        assert_eq!(grid(&boxed.replacen("195", "19", 1)).unwrap_err().line, 3);
+        assert_eq!(grid(&boxed.replacen("..1", "..12", 1)).unwrap_err().column, 16);
+        assert_eq!(
+            grid(&spaced[..spaced.rfind('\n').unwrap()]),
+            Err(ParseError { line: 9, column: 1, reason: "a grid needs 9 rows" })
+        );
+
+        let line = format::to_line(&grid(&spaced).unwrap());
+        let err = grid(&format!("  {}", &line[1..])).unwrap_err();
+        assert_eq!((err.line, err.column), (1, 83));
+    }
+
+    #[test]
+    fn keeps_reading_after_a_bad_puzzle() {
+        let spaced = spaced();
+        let text = format!("{}\n\n{spaced}\n", spaced.replacen("9", "?", 1));
+        let read = grids(&text);
+        assert_eq!(read.len(), 2);
+        assert_eq!(read[0].unwrap_err().line, 2);
+        assert!(read[1].is_ok());
+
+        // a typo in the first row of a grid is one mistake, not nine
+        let text = spaced.replacen('\n', " 1\n", 1);
+        assert_eq!(
+            grids(&text),
+            vec![Err(ParseError { line: 1, column: 19, reason: "a row has only 9 cells" })]
+        );
+    }
+}
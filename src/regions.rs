@@ -0,0 +1,157 @@
+use crate::error::SudokuError;
+
+// The nine houses that take the place of boxes, given as a map of region ids
+// 0..=8. The standard layout is the 3x3 boxes; jigsaw puzzles use any nine
+// connected regions of nine cells.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Regions {
+    map: [[u8; 9]; 9],
+    // the cells of each region in row-major order
+    cells: [[(usize, usize); 9]; 9],
+}
+
+impl Regions {
+    pub fn standard() -> Regions {
+        let map = std::array::from_fn(|r| std::array::from_fn(|c| (3 * (r / 3) + c / 3) as u8));
+        Regions::new(map).expect("the boxes are valid regions")
+    }
+
+    #[allow(clippy::needless_range_loop)]
+    pub fn new(map: [[u8; 9]; 9]) -> Result<Regions, SudokuError> {
+        if map.iter().flatten().any(|&id| id > 8) {
+            return Err(SudokuError::InvalidConstraint { reason: "region ids must be between 0 and 8" });
+        }
+        let mut cells = [[(0, 0); 9]; 9];
+        let mut sizes = [0; 9];
+        for r in 0..9 {
+            for c in 0..9 {
+                let id = map[r][c] as usize;
+                if sizes[id] == 9 {
+                    return Err(SudokuError::InvalidConstraint { reason: "every region must have nine cells" });
+                }
+                cells[id][sizes[id]] = (r, c);
+                sizes[id] += 1;
+            }
+        }
+        // 81 cells, none of the regions over nine, so all of them are exactly nine
+        let regions = Regions { map, cells };
+        if !(0..9).all(|id| regions.is_connected(id)) {
+            return Err(SudokuError::InvalidConstraint { reason: "every region must be connected" });
+        }
+        Ok(regions)
+    }
+
+    // Reads nine lines of nine symbols, one per cell, where cells with the
+    // same symbol share a region. Blank lines and spaces are ignored, so
+    // digits, letters or any other characters can name the regions.
+    pub fn parse(text: &str) -> Result<Regions, SudokuError> {
+        let rows: Vec<Vec<char>> = text.lines()
+            .map(|line| line.chars().filter(|ch| !ch.is_whitespace()).collect::<Vec<_>>())
+            .filter(|row| !row.is_empty())
+            .collect();
+        if rows.len() != 9 || rows.iter().any(|row| row.len() != 9) {
+            return Err(SudokuError::InvalidConstraint { reason: "a region map must have nine rows of nine cells" });
+        }
+
+        let mut symbols: Vec<char> = Vec::new();
+        let mut map = [[0u8; 9]; 9];
+        for (r, row) in rows.iter().enumerate() {
+            for (c, &symbol) in row.iter().enumerate() {
+                let id = match symbols.iter().position(|&s| s == symbol) {
+                    Some(id) => id,
+                    None => { symbols.push(symbol); symbols.len() - 1 },
+                };
+                if id > 8 {
+                    return Err(SudokuError::InvalidConstraint { reason: "a region map must have nine regions" });
+                }
+                map[r][c] = id as u8;
+            }
+        }
+        Regions::new(map)
+    }
+
+    pub fn region(&self, row: usize, col: usize) -> usize {
+        self.map[row][col] as usize
+    }
+
+    pub fn cells(&self, region: usize) -> &[(usize, usize); 9] {
+        &self.cells[region]
+    }
+
+    pub fn map(&self) -> &[[u8; 9]; 9] {
+        &self.map
+    }
+
+    // Whether two cells share a row, column or region.
+    pub fn sees(&self, (r1, c1): (usize, usize), (r2, c2): (usize, usize)) -> bool {
+        r1 == r2 || c1 == c2 || self.map[r1][c1] == self.map[r2][c2]
+    }
+
+    fn is_connected(&self, region: usize) -> bool {
+        let cells = self.cells(region);
+        let mut reached = vec![cells[0]];
+        let mut i = 0;
+        while i < reached.len() {
+            let (r, c) = reached[i];
+            for &cell in cells {
+                let adjacent = r.abs_diff(cell.0) + c.abs_diff(cell.1) == 1;
+                if adjacent && !reached.contains(&cell) { reached.push(cell); }
+            }
+            i += 1;
+        }
+        reached.len() == 9
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JIGSAW: &str = "
+        AAABBBCCC
+        AAABBBCCC
+        AADBBBCCC
+        ADDEEEFFF
+        DDDEEEFFF
+        DDDEEEFFF
+        GGGHHHIII
+        GGGHHHIII
+        GGGHHHIII
+    ";
+
+    #[test]
+    fn parses_region_map() {
+        let regions = Regions::parse(JIGSAW).unwrap();
+        assert_eq!(regions.region(2, 2), 3);
+        assert_eq!(regions.region(3, 0), 0);
+        assert!(regions.cells(0).contains(&(3, 0)));
+        assert!(!regions.sees((2, 2), (1, 1)));
+        assert_eq!(Regions::standard().region(4, 7), 5);
+    }
+
+    #[test]
+    fn rejects_bad_region_maps() {
+        // region A takes (5, 0), which doesn't touch the rest of it
+        let disconnected = "
+            AAABBBCCC
+            AAABBBCCC
+            AADBBBCCC
+            DDDEEEFFF
+            DDDEEEFFF
+            ADDEEEFFF
+            GGGHHHIII
+            GGGHHHIII
+            GGGHHHIII
+        ";
+        assert_eq!(
+            Regions::parse(disconnected),
+            Err(SudokuError::InvalidConstraint { reason: "every region must be connected" })
+        );
+        let uneven = JIGSAW.replacen("AAABBBCCC", "AAAABBCCC", 1);
+        assert_eq!(
+            Regions::parse(&uneven),
+            Err(SudokuError::InvalidConstraint { reason: "every region must have nine cells" })
+        );
+        assert!(Regions::parse("AAABBBCCC").is_err());
+    }
+}
@@ -0,0 +1,366 @@
+use std::fmt;
+use std::rc::Rc;
+use crate::dots::Dots;
+use crate::error::{self, SudokuError};
+use crate::inequality::Inequalities;
+use crate::killer::Cage;
+use crate::lines::Line;
+use crate::outside::OutsideClue;
+use crate::regions::Regions;
+use crate::wavefunc::{BitSet, Sudoku};
+
+// A rule on top of the row, column and region constraints. Constraints
+// can make more cells see each other, reject states and narrow candidates.
+// Every method has a default that does nothing, so a rule only implements
+// the parts it needs; `Rules::add` takes rules from outside this crate too.
+// Only the `wavefunc` solver consults constraints.
+pub trait Constraint: fmt::Debug {
+    // Cells that may not share a value with (row, col) because of this rule.
+    // Placing a digit takes it off all of them.
+    fn peers(&self, _row: usize, _col: usize) -> Vec<(usize, usize)> {
+        Vec::new()
+    }
+
+    // Whether a state of the search can still lead to a solution, checked
+    // after every placement.
+    fn is_valid(&self, _sudoku: &Sudoku) -> bool {
+        true
+    }
+
+    // Takes candidates the rule rules out off the grid, for instance with
+    // `restrict`. Called after every placement until no rule changes
+    // anything more, so it only has to make progress a step at a time.
+    fn prune(&self, sudoku: &Sudoku) -> Sudoku {
+        *sudoku
+    }
+}
+
+// Whether every cell can take a different digit of `digits` from its own
+// candidates.
+pub fn fits(candidates: &[BitSet], digits: BitSet) -> bool {
+    match candidates.split_first() {
+        None => true,
+        Some((first, rest)) => first.intersection(digits).iter()
+            .any(|n| fits(rest, digits.remove(n))),
+    }
+}
+
+// Removes every candidate of every cell that isn't in `allowed`.
+pub fn restrict(sudoku: &Sudoku, cells: &[(usize, usize)], allowed: &[BitSet]) -> Sudoku {
+    let mut sudoku = *sudoku;
+    for (&(r, c), allowed) in cells.iter().zip(allowed) {
+        for n in sudoku.cell(r, c).possible_values().iter().filter(|&n| !allowed.contains(n)) {
+            sudoku = sudoku.without(r, c, n);
+        }
+    }
+    sudoku
+}
+
+// Reads a cell written like r1c1, counting rows and columns from 1.
+pub(crate) fn parse_cell(word: &str) -> Option<(usize, usize)> {
+    let word = word.to_lowercase();
+    let (row, col) = word.strip_prefix('r')?.split_once('c')?;
+    let (row, col): (usize, usize) = (row.parse().ok()?, col.parse().ok()?);
+    if row == 0 || col == 0 { return None; }
+    Some((row - 1, col - 1))
+}
+
+// The rows of a list of clues that hold something, each with its line
+// number, in lower case and without the comment after a `#`. The variant
+// parsers read one clue per row through this.
+pub(crate) fn clue_rows(text: &str) -> impl Iterator<Item = (usize, String)> + '_ {
+    text.lines()
+        .enumerate()
+        .map(|(i, row)| (i + 1, row.split('#').next().unwrap_or("").trim().to_lowercase()))
+        .filter(|(_, row)| !row.is_empty())
+}
+
+// Two cells that may not hold the same value.
+pub type Pair = ((usize, usize), (usize, usize));
+
+// The peers of every cell outside its row, column and region, for the
+// solvers that only track those. Empty when no cell has any, so that they
+// can skip the check. Clones share the table, so solvers built from the
+// same rules don't copy it.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ExtraPeers(Rc<Vec<Vec<(usize, usize)>>>);
+
+impl ExtraPeers {
+    pub(crate) fn of(&self, row: usize, col: usize) -> &[(usize, usize)] {
+        if self.0.is_empty() { return &[]; }
+        &self.0[row * 9 + col]
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+const KNIGHT_MOVES: [(isize, isize); 8] = [(-2, -1), (-2, 1), (-1, -2), (-1, 2), (1, -2), (1, 2), (2, -1), (2, 1)];
+const KING_MOVES: [(isize, isize); 8] = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)];
+
+// The standard rules, or a jigsaw layout, plus any extra houses and variant
+// constraints, for 9x9 grids.
+#[derive(Debug)]
+pub struct Rules {
+    regions: Regions,
+    // all-different groups of nine cells besides the rows, columns and regions
+    houses: Vec<[(usize, usize); 9]>,
+    // equal digits may not be a chess knight's or king's move apart
+    anti_knight: bool,
+    anti_king: bool,
+    constraints: Vec<Box<dyn Constraint>>,
+    // every cell's peers, indexed by row * 9 + col
+    peers: Vec<Vec<(usize, usize)>>,
+    extra_peers: ExtraPeers,
+}
+
+impl Rules {
+    pub fn standard() -> Rules {
+        Rules::jigsaw(Regions::standard())
+    }
+
+    pub fn jigsaw(regions: Regions) -> Rules {
+        let mut rules = Rules {
+            regions,
+            houses: Vec::new(),
+            anti_knight: false,
+            anti_king: false,
+            constraints: Vec::new(),
+            peers: Vec::new(),
+            extra_peers: ExtraPeers::default(),
+        };
+        rules.update_peers();
+        rules
+    }
+
+    pub fn regions(&self) -> &Regions {
+        &self.regions
+    }
+
+    pub fn houses(&self) -> &[[(usize, usize); 9]] {
+        &self.houses
+    }
+
+    pub fn add_house(&mut self, cells: [(usize, usize); 9]) -> Result<(), SudokuError> {
+        if let Some(&(row, col)) = cells.iter().find(|&&(r, c)| r >= 9 || c >= 9) {
+            return Err(SudokuError::IndexOutOfRange { row, col, size: 9 });
+        }
+        if cells.iter().enumerate().any(|(i, cell)| cells[..i].contains(cell)) {
+            return Err(SudokuError::InvalidConstraint { reason: "a house may not repeat a cell" });
+        }
+        self.houses.push(cells);
+        self.update_peers();
+        Ok(())
+    }
+
+    // The two main diagonals, as in X-sudoku.
+    pub fn add_diagonals(&mut self) {
+        self.add_house(std::array::from_fn(|i| (i, i))).unwrap();
+        self.add_house(std::array::from_fn(|i| (i, 8 - i))).unwrap();
+    }
+
+    // The four 3x3 windows of Windoku, each one in from a corner.
+    pub fn add_windoku(&mut self) {
+        for (top, left) in [(1, 1), (1, 5), (5, 1), (5, 5)] {
+            self.add_house(std::array::from_fn(|i| (top + i / 3, left + i % 3))).unwrap();
+        }
+    }
+
+    // The centre cells of the nine boxes.
+    pub fn add_centre_dot(&mut self) {
+        self.add_house(std::array::from_fn(|i| (3 * (i / 3) + 1, 3 * (i % 3) + 1))).unwrap();
+    }
+
+    pub fn add_anti_knight(&mut self) {
+        self.anti_knight = true;
+        self.update_peers();
+    }
+
+    pub fn add_anti_king(&mut self) {
+        self.anti_king = true;
+        self.update_peers();
+    }
+
+    // Whether there is more to the rules than the layout of the houses.
+    pub fn has_constraints(&self) -> bool {
+        !self.constraints.is_empty()
+    }
+
+    pub fn add_cage(&mut self, cage: Cage) {
+        self.add(cage);
+    }
+
+    pub fn add_line(&mut self, line: Line) {
+        self.add(line);
+    }
+
+    pub fn add_outside_clue(&mut self, clue: OutsideClue) {
+        self.add(clue);
+    }
+
+    pub fn add_dots(&mut self, dots: Dots) {
+        self.add(dots);
+    }
+
+    pub fn add_inequalities(&mut self, inequalities: Inequalities) {
+        self.add(inequalities);
+    }
+
+    pub fn add(&mut self, constraint: impl Constraint + 'static) {
+        self.constraints.push(Box::new(constraint));
+        self.update_peers();
+    }
+
+    fn update_peers(&mut self) {
+        self.peers = (0..81).map(|i| {
+            let (row, col) = (i / 9, i % 9);
+            let mut peers: Vec<(usize, usize)> = (0..81)
+                .map(|j| (j / 9, j % 9))
+                .filter(|&cell| self.regions.sees(cell, (row, col)) && cell != (row, col))
+                .collect();
+            for house in self.houses.iter().filter(|house| house.contains(&(row, col))) {
+                for &peer in house {
+                    if peer != (row, col) && !peers.contains(&peer) { peers.push(peer); }
+                }
+            }
+            let mut moves: Vec<(isize, isize)> = Vec::new();
+            if self.anti_knight { moves.extend(KNIGHT_MOVES); }
+            if self.anti_king { moves.extend(KING_MOVES); }
+            for (dr, dc) in moves {
+                let (r, c) = (row as isize + dr, col as isize + dc);
+                if !(0..9).contains(&r) || !(0..9).contains(&c) { continue; }
+                let peer = (r as usize, c as usize);
+                if !peers.contains(&peer) { peers.push(peer); }
+            }
+            for constraint in &self.constraints {
+                for peer in constraint.peers(row, col) {
+                    if peer != (row, col) && !peers.contains(&peer) { peers.push(peer); }
+                }
+            }
+            peers
+        }).collect();
+        let extra_peers: Vec<_> = (0..81).map(|i| self.extra_peers(i / 9, i % 9)).collect();
+        self.extra_peers = if extra_peers.iter().all(|peers| peers.is_empty()) { ExtraPeers::default() }
+            else { ExtraPeers(Rc::new(extra_peers)) };
+    }
+
+    pub fn peers(&self, row: usize, col: usize) -> &[(usize, usize)] {
+        &self.peers[row * 9 + col]
+    }
+
+    // The peers that don't share a row, column or region with (row, col).
+    pub fn extra_peers(&self, row: usize, col: usize) -> Vec<(usize, usize)> {
+        self.peers(row, col).iter()
+            .copied()
+            .filter(|&cell| !self.regions.sees(cell, (row, col)))
+            .collect()
+    }
+
+    // Pairs of peers, each listed once, that no row, column, region or extra
+    // house has in common. These only have to differ from each other.
+    pub fn pairwise_peers(&self) -> Vec<Pair> {
+        let mut pairs = Vec::new();
+        for row in 0..9 {
+            for col in 0..9 {
+                for peer in self.extra_peers(row, col) {
+                    let in_house = self.houses.iter()
+                        .any(|house| house.contains(&(row, col)) && house.contains(&peer));
+                    if peer > (row, col) && !in_house { pairs.push(((row, col), peer)); }
+                }
+            }
+        }
+        pairs
+    }
+
+    // `extra_peers` of every cell, worked out once when the rules change.
+    pub(crate) fn all_extra_peers(&self) -> &ExtraPeers {
+        &self.extra_peers
+    }
+
+    // Like `error::validate`, but with the houses of these rules and the
+    // cells constraints make see each other.
+    pub fn validate(&self, grid: &[[u8; 9]; 9]) -> Result<(), SudokuError> {
+        error::validate_with(grid, |(r, c), cell| self.peers(r, c).contains(&cell))
+    }
+
+    pub fn is_valid(&self, sudoku: &Sudoku) -> bool {
+        sudoku.is_valid()
+            && self.constraints.iter().all(|constraint| constraint.is_valid(sudoku))
+    }
+
+    // Applies every constraint's pruning until none of them changes anything.
+    pub fn prune(&self, sudoku: &Sudoku) -> Sudoku {
+        let mut sudoku = *sudoku;
+        loop {
+            let pruned = self.constraints.iter()
+                .fold(sudoku, |sudoku, constraint| constraint.prune(&sudoku));
+            if pruned == sudoku || !pruned.is_valid() { return pruned; }
+            sudoku = pruned;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the listed cells hold odd digits
+    #[derive(Debug)]
+    struct Odd(Vec<(usize, usize)>);
+
+    impl Constraint for Odd {
+        fn prune(&self, sudoku: &Sudoku) -> Sudoku {
+            let odd = [1, 3, 5, 7, 9].into_iter().fold(BitSet::new(), |set, n| set.insert(n));
+            restrict(sudoku, &self.0, &vec![odd; self.0.len()])
+        }
+    }
+
+    // the two cells differ
+    #[derive(Debug)]
+    struct Apart((usize, usize), (usize, usize));
+
+    impl Constraint for Apart {
+        fn peers(&self, row: usize, col: usize) -> Vec<(usize, usize)> {
+            if (row, col) == self.0 { vec![self.1] }
+            else if (row, col) == self.1 { vec![self.0] }
+            else { Vec::new() }
+        }
+    }
+
+    // the first row counts down from 9
+    #[derive(Debug)]
+    struct Countdown;
+
+    impl Constraint for Countdown {
+        fn is_valid(&self, sudoku: &Sudoku) -> bool {
+            sudoku.grid()[0].iter().enumerate().all(|(c, &n)| n == 0 || n as usize == 9 - c)
+        }
+    }
+
+    #[test]
+    fn takes_constraints_from_outside() {
+        let mut rules = Rules::standard();
+        rules.add(Odd(vec![(4, 4), (4, 5)]));
+        rules.add(Apart((0, 0), (8, 8)));
+        rules.add(Countdown);
+        assert!(rules.peers(8, 8).contains(&(0, 0)));
+
+        let mut grid = [[0u8; 9]; 9];
+        grid[8][8] = 9;
+        assert_eq!(rules.validate(&grid), Ok(()));
+        grid[0][0] = 9;
+        assert_eq!(
+            rules.validate(&grid),
+            Err(SudokuError::Conflict { row: 8, col: 8, value: 9, with: (0, 0) })
+        );
+
+        let solved = Sudoku::from_rules([[0u8; 9]; 9], &rules).unwrap()
+            .solved_with(&rules)
+            .unwrap()
+            .grid();
+        assert_eq!(solved[0], [9, 8, 7, 6, 5, 4, 3, 2, 1]);
+        assert!(solved[4][4] % 2 == 1 && solved[4][5] % 2 == 1);
+        assert_ne!(solved[0][0], solved[8][8]);
+    }
+}
@@ -0,0 +1,322 @@
+use std::fmt;
+use crate::error::SudokuError;
+use crate::wavefunc::BitSet;
+
+// Where the 9x9 grids of a multi-grid puzzle sit on a shared canvas, by the
+// position of their top left cell. Grids overlap in whole boxes, and a cell
+// covered by several grids is one cell of every one of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    grids: Vec<(usize, usize)>,
+    height: usize,
+    width: usize,
+    // every cell's peers across all the grids, indexed by row * width + col
+    peers: Vec<Vec<usize>>,
+}
+
+impl Layout {
+    pub fn new(grids: Vec<(usize, usize)>) -> Result<Layout, SudokuError> {
+        if grids.is_empty() {
+            return Err(SudokuError::InvalidConstraint { reason: "a layout needs at least one grid" });
+        }
+        if grids.iter().any(|&(r, c)| r % 3 != 0 || c % 3 != 0) {
+            return Err(SudokuError::InvalidConstraint { reason: "grids must line up with each other's boxes" });
+        }
+        if grids.iter().enumerate().any(|(i, grid)| grids[..i].contains(grid)) {
+            return Err(SudokuError::InvalidConstraint { reason: "a layout may not repeat a grid" });
+        }
+        let height = grids.iter().map(|&(r, _)| r + 9).max().unwrap_or(0);
+        let width = grids.iter().map(|&(_, c)| c + 9).max().unwrap_or(0);
+
+        let mut peers = vec![Vec::new(); height * width];
+        for &(top, left) in &grids {
+            for i in 0..81 {
+                let (r, c) = (i / 9, i % 9);
+                for j in (0..81).filter(|&j| j != i) {
+                    let (r2, c2) = (j / 9, j % 9);
+                    let sees = r == r2 || c == c2 || (r / 3 == r2 / 3 && c / 3 == c2 / 3);
+                    let peer = (top + r2) * width + left + c2;
+                    let cell = &mut peers[(top + r) * width + left + c];
+                    if sees && !cell.contains(&peer) { cell.push(peer); }
+                }
+            }
+        }
+        Ok(Layout { grids, height, width, peers })
+    }
+
+    // Five grids, four around the corners of the one in the middle, each
+    // sharing a box with it.
+    pub fn samurai() -> Layout {
+        Layout::new(vec![(0, 0), (0, 12), (6, 6), (12, 0), (12, 12)]).unwrap()
+    }
+
+    // Two grids sharing a corner box.
+    pub fn twin() -> Layout {
+        Layout::new(vec![(0, 0), (6, 6)]).unwrap()
+    }
+
+    // Four grids overlapping in a 12x12 square.
+    pub fn butterfly() -> Layout {
+        Layout::new(vec![(0, 0), (0, 3), (3, 0), (3, 3)]).unwrap()
+    }
+
+    pub fn grids(&self) -> &[(usize, usize)] {
+        &self.grids
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    // Whether (row, col) of the canvas belongs to any grid.
+    pub fn contains(&self, row: usize, col: usize) -> bool {
+        self.grids.iter().any(|&(top, left)| (top..top + 9).contains(&row) && (left..left + 9).contains(&col))
+    }
+
+    fn cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.height)
+            .flat_map(move |r| (0..self.width).map(move |c| (r, c)))
+            .filter(|&(r, c)| self.contains(r, c))
+    }
+}
+
+// A puzzle on several overlapping 9x9 grids, such as Samurai sudoku. Digits
+// are kept on the whole canvas, with 0 for empty cells and for the cells
+// that no grid covers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiSudoku {
+    layout: Layout,
+    grid: Vec<Vec<u8>>,
+}
+
+// A digit in a gap between the grids of a layout.
+const OUTSIDE: SudokuError = SudokuError::InvalidConstraint { reason: "a digit lies outside every grid of the layout" };
+
+impl MultiSudoku {
+    pub fn from(layout: Layout, grid: Vec<Vec<u8>>) -> Result<MultiSudoku, SudokuError> {
+        if grid.len() != layout.height || grid.iter().any(|row| row.len() != layout.width) {
+            return Err(SudokuError::InvalidConstraint { reason: "the grid doesn't have the size of the layout" });
+        }
+        for (row, cells) in grid.iter().enumerate() {
+            for (col, &value) in cells.iter().enumerate() {
+                if value > 9 { return Err(SudokuError::ValueOutOfRange { row, col, value, max: 9 }); }
+                if value != 0 && !layout.contains(row, col) { return Err(OUTSIDE); }
+            }
+        }
+        for (row, col) in layout.cells() {
+            let value = grid[row][col];
+            if value == 0 { continue; }
+            let earlier_peer = layout.peers[row * layout.width + col].iter()
+                .map(|&peer| (peer / layout.width, peer % layout.width))
+                .filter(|&cell| cell < (row, col) && grid[cell.0][cell.1] == value)
+                .min();
+            if let Some(with) = earlier_peer {
+                return Err(SudokuError::Conflict { row, col, value, with });
+            }
+        }
+        Ok(MultiSudoku { layout, grid })
+    }
+
+    // Reads the canvas one row per line, with a digit or `.` for every cell
+    // of a grid and spaces where there is none. Blank lines are skipped.
+    pub fn parse(layout: Layout, text: &str) -> Result<MultiSudoku, SudokuError> {
+        let rows: Vec<Vec<char>> = text.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.trim_end().chars().collect())
+            .collect();
+        if rows.len() != layout.height || rows.iter().any(|row| row.len() > layout.width) {
+            return Err(SudokuError::InvalidConstraint { reason: "the text doesn't have the size of the layout" });
+        }
+        let mut grid = vec![vec![0u8; layout.width]; layout.height];
+        for (r, (row, cells)) in rows.iter().zip(grid.iter_mut()).enumerate() {
+            for (c, cell) in cells.iter_mut().enumerate() {
+                let ch = row.get(c).copied().unwrap_or(' ');
+                *cell = match (layout.contains(r, c), ch) {
+                    (true, '.' | '0') | (false, ' ') => 0,
+                    (true, '1'..='9') => ch as u8 - b'0',
+                    (true, _) => return Err(SudokuError::InvalidConstraint { reason: "cells must hold a digit or '.'" }),
+                    (false, _) => return Err(OUTSIDE),
+                };
+            }
+        }
+        MultiSudoku::from(layout, grid)
+    }
+
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    pub fn grid(&self) -> &[Vec<u8>] {
+        &self.grid
+    }
+
+    // The digits of the `index`th grid of the layout on their own.
+    pub fn subgrid(&self, index: usize) -> [[u8; 9]; 9] {
+        let (top, left) = self.layout.grids[index];
+        std::array::from_fn(|r| std::array::from_fn(|c| self.grid[top + r][left + c]))
+    }
+
+    pub fn solve(&mut self) -> bool {
+        let mut solution = None;
+        self.search(&mut |candidates| {
+            solution = Some(candidates.to_vec());
+            false
+        });
+        match solution {
+            Some(candidates) => {
+                for (r, c) in self.layout.cells() {
+                    self.grid[r][c] = candidates[r * self.layout.width + c].iter().next().unwrap();
+                }
+                true
+            },
+            None => false,
+        }
+    }
+
+    pub fn count_solutions(&self, limit: usize) -> usize {
+        if limit == 0 { return 0; }
+        let mut count = 0;
+        self.search(&mut |_| {
+            count += 1;
+            count < limit
+        });
+        count
+    }
+
+    pub fn is_unique(&self) -> bool {
+        self.count_solutions(2) == 1
+    }
+
+    // Calls `on_solution` with the candidates of every solution until it
+    // returns false.
+    fn search(&self, on_solution: &mut dyn FnMut(&[BitSet]) -> bool) {
+        let mut candidates: Vec<BitSet> = (0..self.layout.height * self.layout.width)
+            .map(|i| if self.layout.contains(i / self.layout.width, i % self.layout.width) { BitSet::full() } else { BitSet::new() })
+            .collect();
+        for (r, c) in self.layout.cells() {
+            let value = self.grid[r][c];
+            if value != 0 && !self.assign(&mut candidates, r * self.layout.width + c, value) { return; }
+        }
+        self.search_from(candidates, on_solution);
+    }
+
+    fn search_from(&self, candidates: Vec<BitSet>, on_solution: &mut dyn FnMut(&[BitSet]) -> bool) -> bool {
+        let cell = (0..candidates.len())
+            .filter(|&i| candidates[i].size() > 1)
+            .min_by_key(|&i| candidates[i].size());
+        let Some(cell) = cell else { return on_solution(&candidates); };
+        for value in candidates[cell].iter() {
+            let mut next = candidates.clone();
+            if self.assign(&mut next, cell, value) && !self.search_from(next, on_solution) { return false; }
+        }
+        true
+    }
+
+    // Places `value` and takes it off every peer, placing in turn any peer
+    // left with one candidate. Returns false if some cell runs out.
+    fn assign(&self, candidates: &mut [BitSet], cell: usize, value: u8) -> bool {
+        let mut queue = vec![(cell, value)];
+        while let Some((cell, value)) = queue.pop() {
+            candidates[cell] = BitSet::from_single(value);
+            for &peer in &self.layout.peers[cell] {
+                if !candidates[peer].contains(value) { continue; }
+                candidates[peer] = candidates[peer].remove(value);
+                match candidates[peer].size() {
+                    0 => return false,
+                    1 => queue.push((peer, candidates[peer].iter().next().unwrap())),
+                    _ => {},
+                }
+            }
+        }
+        true
+    }
+}
+
+impl fmt::Display for MultiSudoku {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (r, row) in self.grid.iter().enumerate() {
+            let line: String = row.iter()
+                .enumerate()
+                .map(|(c, &n)| match n {
+                    _ if !self.layout.contains(r, c) => ' ',
+                    0 => '.',
+                    n => (b'0' + n) as char,
+                })
+                .collect();
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error;
+
+    #[test]
+    fn lays_out_grids() {
+        let samurai = Layout::samurai();
+        assert_eq!((samurai.height(), samurai.width()), (21, 21));
+        assert_eq!(samurai.cells().count(), 5 * 81 - 4 * 9);
+        assert!(!samurai.contains(0, 10));
+        // a corner of the middle box is in two grids
+        assert_eq!(samurai.peers[6 * 21 + 6].len(), 20 + 20 - 8);
+
+        assert_eq!(Layout::butterfly().cells().count(), 144);
+        assert!(Layout::new(vec![(0, 0), (4, 4)]).is_err());
+    }
+
+    #[test]
+    fn solves_every_grid_at_once() {
+        let mut sudoku = MultiSudoku::from(Layout::samurai(), vec![vec![0; 21]; 21]).unwrap();
+        assert!(sudoku.solve());
+        for i in 0..5 {
+            let grid = sudoku.subgrid(i);
+            assert!(error::validate(&grid).is_ok());
+            assert!(grid.iter().flatten().all(|&n| n != 0));
+        }
+    }
+
+    #[test]
+    fn reads_and_writes_the_canvas() {
+        let mut solved = MultiSudoku::from(Layout::twin(), vec![vec![0; 15]; 15]).unwrap();
+        assert!(solved.solve());
+        // one gap per row can only be filled one way
+        let mut grid = solved.grid().to_vec();
+        for (i, row) in grid.iter_mut().enumerate() {
+            row[i] = 0;
+        }
+        let puzzle = MultiSudoku::from(Layout::twin(), grid).unwrap();
+        let text = puzzle.to_string();
+        assert_eq!(text.lines().next().unwrap().len(), 9);
+        assert_eq!(&text.lines().nth(6).unwrap()[6..7], ".");
+        assert_eq!(MultiSudoku::parse(Layout::twin(), &text).unwrap(), puzzle);
+
+        assert!(puzzle.is_unique());
+        let mut puzzle = puzzle;
+        assert!(puzzle.solve());
+        assert_eq!(puzzle, solved);
+    }
+
+    #[test]
+    fn rejects_conflicts_between_grids() {
+        // the shared box is in row 6 of both grids, so the two 4s clash in
+        // the second grid
+        let mut grid = vec![vec![0u8; 15]; 15];
+        grid[6][6] = 4;
+        grid[6][14] = 4;
+        assert_eq!(
+            MultiSudoku::from(Layout::twin(), grid.clone()),
+            Err(SudokuError::Conflict { row: 6, col: 14, value: 4, with: (6, 6) })
+        );
+        grid[6][14] = 0;
+        grid[0][14] = 1;
+        assert_eq!(MultiSudoku::from(Layout::twin(), grid), Err(OUTSIDE));
+        assert!(MultiSudoku::parse(Layout::twin(), "123").is_err());
+    }
+}
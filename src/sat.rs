@@ -0,0 +1,451 @@
+use std::{fmt::Write as _, io};
+use crate::solver::{self, SolveOutcome};
+use crate::error::{self, SudokuError};
+use crate::regions::Regions;
+use crate::rules::{Pair, Rules};
+
+// Variables follow the DIMACS convention: numbered from 1, a positive
+// literal asserts the variable and a negative one its negation. Value `n`
+// in cell (r, c) is variable `r * 81 + c * 9 + n`.
+pub fn var(r: usize, c: usize, n: u8) -> i32 {
+    (r * 81 + c * 9 + n as usize) as i32
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cnf {
+    num_vars: usize,
+    clauses: Vec<Vec<i32>>,
+}
+
+impl Cnf {
+    pub fn new(num_vars: usize) -> Cnf {
+        Cnf { num_vars, clauses: Vec::new() }
+    }
+
+    pub fn from_grid(grid: [[u8; 9]; 9]) -> Result<Cnf, SudokuError> {
+        error::validate(&grid)?;
+        Ok(Cnf::encode(grid, &Regions::standard(), &[], &[]))
+    }
+
+    pub fn from_rules(grid: [[u8; 9]; 9], rules: &Rules) -> Result<Cnf, SudokuError> {
+        rules.validate(&grid)?;
+        Ok(Cnf::encode(grid, rules.regions(), rules.houses(), &rules.pairwise_peers()))
+    }
+
+    #[allow(clippy::needless_range_loop)]
+    fn encode(
+        grid: [[u8; 9]; 9],
+        regions: &Regions,
+        extra_houses: &[[(usize, usize); 9]],
+        pairs: &[Pair],
+    ) -> Cnf {
+        let mut cnf = Cnf::new(729);
+
+        let mut houses: Vec<Vec<(usize, usize)>> = Vec::new();
+        for i in 0..9 {
+            houses.push((0..9).map(|j| (i, j)).collect());
+            houses.push((0..9).map(|j| (j, i)).collect());
+            houses.push(regions.cells(i).to_vec());
+        }
+        houses.extend(extra_houses.iter().map(|house| house.to_vec()));
+
+        for r in 0..9 {
+            for c in 0..9 {
+                cnf.push_exactly_one(&(1..=9).map(|n| var(r, c, n)).collect::<Vec<_>>());
+                if grid[r][c] != 0 { cnf.clauses.push(vec![var(r, c, grid[r][c])]); }
+            }
+        }
+        for house in &houses {
+            for n in 1..=9 {
+                cnf.push_exactly_one(&house.iter().map(|&(r, c)| var(r, c, n)).collect::<Vec<_>>());
+            }
+        }
+        for &((r1, c1), (r2, c2)) in pairs {
+            for n in 1..=9 {
+                cnf.clauses.push(vec![-var(r1, c1, n), -var(r2, c2, n)]);
+            }
+        }
+        cnf
+    }
+
+    pub fn num_vars(&self) -> usize {
+        self.num_vars
+    }
+
+    pub fn clauses(&self) -> &[Vec<i32>] {
+        &self.clauses
+    }
+
+    // Fails if a literal is 0 or names a variable past `num_vars`.
+    pub fn add_clause(&mut self, clause: Vec<i32>) -> Result<(), SudokuError> {
+        self.check(&clause)?;
+        self.clauses.push(clause);
+        Ok(())
+    }
+
+    pub fn exactly_one(&mut self, lits: &[i32]) -> Result<(), SudokuError> {
+        self.check(lits)?;
+        self.push_exactly_one(lits);
+        Ok(())
+    }
+
+    // at least one of `lits` with a clause, at most one with pairwise exclusion
+    fn push_exactly_one(&mut self, lits: &[i32]) {
+        self.clauses.push(lits.to_vec());
+        for (i, a) in lits.iter().enumerate() {
+            for b in &lits[i + 1..] {
+                self.clauses.push(vec![-a, -b]);
+            }
+        }
+    }
+
+    fn check(&self, lits: &[i32]) -> Result<(), SudokuError> {
+        if lits.iter().any(|&lit| lit == 0 || lit.unsigned_abs() as usize > self.num_vars) {
+            return Err(SudokuError::InvalidConstraint { reason: "a literal must name one of the variables" });
+        }
+        Ok(())
+    }
+
+    pub fn to_dimacs(&self) -> String {
+        let mut s = format!("p cnf {} {}\n", self.num_vars, self.clauses.len());
+        for clause in &self.clauses {
+            for lit in clause {
+                write!(s, "{lit} ").unwrap();
+            }
+            s.push_str("0\n");
+        }
+        s
+    }
+
+    pub fn write_dimacs(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        writer.write_all(self.to_dimacs().as_bytes())
+    }
+
+    // A satisfying assignment indexed by variable - 1, or `None` if the
+    // formula is unsatisfiable.
+    pub fn solve(&self) -> Option<Vec<bool>> {
+        Cdcl::new(self)?.solve()
+    }
+}
+
+// The grid a model of the sudoku encoding stands for, or `None` if the
+// model is too short to cover every cell.
+pub fn decode(model: &[bool]) -> Option<[[u8; 9]; 9]> {
+    if model.len() < 729 { return None; }
+    let mut grid = [[0u8; 9]; 9];
+    for r in 0..9 {
+        for c in 0..9 {
+            if let Some(n) = (1..=9).find(|&n| model[var(r, c, n) as usize - 1]) {
+                grid[r][c] = n;
+            }
+        }
+    }
+    Some(grid)
+}
+
+// Internally literals are 2 * var + sign with 0-based variables, so that a
+// literal and its negation differ in the lowest bit.
+type Lit = usize;
+
+fn lit_from(dimacs: i32) -> Lit {
+    2 * (dimacs.unsigned_abs() as usize - 1) + (dimacs < 0) as usize
+}
+
+fn var_of(lit: Lit) -> usize {
+    lit >> 1
+}
+
+// Conflict-driven clause learning with two watched literals, first-UIP
+// learning, backjumping and activity-based branching.
+struct Cdcl {
+    clauses: Vec<Vec<Lit>>,
+    // clauses in which the literal is one of the two watched ones
+    watches: Vec<Vec<usize>>,
+    assigns: Vec<Option<bool>>,
+    level: Vec<usize>,
+    reason: Vec<Option<usize>>,
+    trail: Vec<Lit>,
+    trail_lim: Vec<usize>,
+    propagated: usize,
+    activity: Vec<f64>,
+    bump: f64,
+}
+
+impl Cdcl {
+    // Returns `None` if the clauses are trivially unsatisfiable.
+    fn new(cnf: &Cnf) -> Option<Cdcl> {
+        let mut solver = Cdcl {
+            clauses: Vec::new(),
+            watches: vec![Vec::new(); 2 * cnf.num_vars],
+            assigns: vec![None; cnf.num_vars],
+            level: vec![0; cnf.num_vars],
+            reason: vec![None; cnf.num_vars],
+            trail: Vec::new(),
+            trail_lim: Vec::new(),
+            propagated: 0,
+            activity: vec![0.0; cnf.num_vars],
+            bump: 1.0,
+        };
+        for clause in &cnf.clauses {
+            let mut lits: Vec<Lit> = clause.iter().map(|&lit| lit_from(lit)).collect();
+            lits.sort();
+            lits.dedup();
+            if lits.windows(2).any(|pair| pair[0] ^ 1 == pair[1]) { continue; }
+            match lits.len() {
+                0 => return None,
+                1 => match solver.value(lits[0]) {
+                    Some(false) => return None,
+                    Some(true) => {},
+                    None => solver.assign(lits[0], None),
+                },
+                _ => { solver.attach(lits); },
+            }
+        }
+        Some(solver)
+    }
+
+    fn value(&self, lit: Lit) -> Option<bool> {
+        self.assigns[var_of(lit)].map(|val| val != (lit & 1 == 1))
+    }
+
+    fn decision_level(&self) -> usize {
+        self.trail_lim.len()
+    }
+
+    fn assign(&mut self, lit: Lit, reason: Option<usize>) {
+        let var = var_of(lit);
+        self.assigns[var] = Some(lit & 1 == 0);
+        self.level[var] = self.decision_level();
+        self.reason[var] = reason;
+        self.trail.push(lit);
+    }
+
+    fn attach(&mut self, lits: Vec<Lit>) -> usize {
+        let index = self.clauses.len();
+        self.watches[lits[0]].push(index);
+        self.watches[lits[1]].push(index);
+        self.clauses.push(lits);
+        index
+    }
+
+    // Returns the index of a conflicting clause, if any.
+    fn propagate(&mut self) -> Option<usize> {
+        while self.propagated < self.trail.len() {
+            let false_lit = self.trail[self.propagated] ^ 1;
+            self.propagated += 1;
+
+            let watching = std::mem::take(&mut self.watches[false_lit]);
+            let mut kept = Vec::with_capacity(watching.len());
+            let mut conflict = None;
+            for (i, &index) in watching.iter().enumerate() {
+                if conflict.is_some() {
+                    kept.extend_from_slice(&watching[i..]);
+                    break;
+                }
+                let clause = &mut self.clauses[index];
+                if clause[0] == false_lit { clause.swap(0, 1); }
+                let first = clause[0];
+                if self.value(first) == Some(true) {
+                    kept.push(index);
+                    continue;
+                }
+
+                let clause = &self.clauses[index];
+                match (2..clause.len()).find(|&k| self.value(clause[k]) != Some(false)) {
+                    Some(k) => {
+                        self.clauses[index].swap(1, k);
+                        let watch = self.clauses[index][1];
+                        self.watches[watch].push(index);
+                    },
+                    None => {
+                        kept.push(index);
+                        if self.value(first) == Some(false) { conflict = Some(index); }
+                        else { self.assign(first, Some(index)); }
+                    },
+                }
+            }
+            self.watches[false_lit] = kept;
+            if conflict.is_some() { return conflict; }
+        }
+        None
+    }
+
+    // First-UIP conflict analysis. Returns the learnt clause, asserting
+    // literal first, and the level to backjump to.
+    fn analyze(&mut self, conflict: usize) -> (Vec<Lit>, usize) {
+        let mut seen = vec![false; self.assigns.len()];
+        let mut learnt: Vec<Lit> = vec![0];
+        let mut pending = 0;
+        let mut index = self.trail.len();
+        let mut clause = conflict;
+        let mut skip_first = false;
+
+        loop {
+            let start = skip_first as usize;
+            for k in start..self.clauses[clause].len() {
+                let lit = self.clauses[clause][k];
+                let var = var_of(lit);
+                if seen[var] || self.level[var] == 0 { continue; }
+                seen[var] = true;
+                self.bump_activity(var);
+                if self.level[var] == self.decision_level() { pending += 1; }
+                else { learnt.push(lit); }
+            }
+            loop {
+                index -= 1;
+                if seen[var_of(self.trail[index])] { break; }
+            }
+            let lit = self.trail[index];
+            seen[var_of(lit)] = false;
+            pending -= 1;
+            if pending == 0 {
+                learnt[0] = lit ^ 1;
+                break;
+            }
+            clause = self.reason[var_of(lit)].expect("implied literal without a reason");
+            skip_first = true;
+        }
+
+        let mut backjump = 0;
+        if learnt.len() > 1 {
+            let max = (1..learnt.len())
+                .max_by_key(|&k| self.level[var_of(learnt[k])])
+                .unwrap();
+            learnt.swap(1, max);
+            backjump = self.level[var_of(learnt[1])];
+        }
+        (learnt, backjump)
+    }
+
+    fn bump_activity(&mut self, var: usize) {
+        self.activity[var] += self.bump;
+        if self.activity[var] > 1e100 {
+            self.activity.iter_mut().for_each(|activity| *activity *= 1e-100);
+            self.bump *= 1e-100;
+        }
+    }
+
+    fn backjump(&mut self, level: usize) {
+        if self.decision_level() <= level { return; }
+        for &lit in &self.trail[self.trail_lim[level]..] {
+            self.assigns[var_of(lit)] = None;
+            self.reason[var_of(lit)] = None;
+        }
+        self.trail.truncate(self.trail_lim[level]);
+        self.trail_lim.truncate(level);
+        self.propagated = self.trail.len();
+    }
+
+    fn solve(mut self) -> Option<Vec<bool>> {
+        loop {
+            if let Some(conflict) = self.propagate() {
+                if self.decision_level() == 0 { return None; }
+                let (learnt, level) = self.analyze(conflict);
+                self.backjump(level);
+                if learnt.len() == 1 {
+                    self.assign(learnt[0], None);
+                } else {
+                    let asserting = learnt[0];
+                    let index = self.attach(learnt);
+                    self.assign(asserting, Some(index));
+                }
+                self.bump /= 0.95;
+                continue;
+            }
+
+            let unassigned = (0..self.assigns.len())
+                .filter(|&var| self.assigns[var].is_none())
+                .max_by(|&a, &b| self.activity[a].total_cmp(&self.activity[b]));
+            match unassigned {
+                None => return Some(self.assigns.iter().map(|val| val.unwrap()).collect()),
+                Some(var) => {
+                    self.trail_lim.push(self.trail.len());
+                    // most variables of a sudoku encoding are false
+                    self.assign(2 * var + 1, None);
+                },
+            }
+        }
+    }
+}
+
+pub struct Sat;
+
+impl solver::Solver for Sat {
+    fn name(&self) -> &'static str {
+        "sat (cdcl)"
+    }
+
+    fn solve(&self, grid: &[[u8; 9]; 9]) -> SolveOutcome {
+        let cnf = match Cnf::from_grid(*grid) {
+            Ok(cnf) => cnf,
+            Err(err) => return SolveOutcome::Invalid(err),
+        };
+        match cnf.solve().and_then(|model| decode(&model)) {
+            Some(grid) => SolveOutcome::Solved(grid),
+            None => SolveOutcome::Unsolvable,
+        }
+    }
+
+    fn solve_with(&self, grid: &[[u8; 9]; 9], rules: &Rules) -> SolveOutcome {
+        if !self.supports(rules) { return solver::UNSUPPORTED; }
+        let cnf = match Cnf::from_rules(*grid, rules) {
+            Ok(cnf) => cnf,
+            Err(err) => return SolveOutcome::Invalid(err),
+        };
+        match cnf.solve().and_then(|model| decode(&model)) {
+            Some(grid) => SolveOutcome::Solved(grid),
+            None => SolveOutcome::Unsolvable,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dimacs_header_and_clauses() {
+        let mut cnf = Cnf::new(3);
+        cnf.add_clause(vec![1, -2]).unwrap();
+        cnf.add_clause(vec![3]).unwrap();
+        assert_eq!(cnf.to_dimacs(), "p cnf 3 2\n1 -2 0\n3 0\n");
+        assert!(cnf.add_clause(vec![4]).is_err());
+        assert!(cnf.add_clause(vec![0, 1]).is_err());
+        assert!(cnf.exactly_one(&[1, -4]).is_err());
+        assert_eq!(cnf.clauses().len(), 2);
+
+        let cnf = Cnf::from_grid([[0u8; 9]; 9]).unwrap();
+        assert!(cnf.to_dimacs().starts_with("p cnf 729 "));
+    }
+
+    #[test]
+    fn pigeonhole_is_unsatisfiable() {
+        // four pigeons, three holes: pigeon p in hole h is variable 3p + h + 1
+        let mut cnf = Cnf::new(12);
+        for p in 0..4 {
+            cnf.add_clause((0..3).map(|h| 3 * p + h + 1).collect()).unwrap();
+        }
+        for h in 0..3 {
+            for p in 0..4 {
+                for q in p + 1..4 {
+                    cnf.add_clause(vec![-(3 * p + h + 1), -(3 * q + h + 1)]).unwrap();
+                }
+            }
+        }
+        assert_eq!(cnf.solve(), None);
+    }
+
+    #[test]
+    fn decodes_solution() {
+        let mut grid = [[0u8; 9]; 9];
+        grid[0] = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+        let model = Cnf::from_grid(grid).unwrap().solve().unwrap();
+        let solution = decode(&model).unwrap();
+        assert_eq!(solution[0], grid[0]);
+        assert!(error::validate(&solution).is_ok());
+        assert!(solution.iter().flatten().all(|&n| n != 0));
+
+        assert_eq!(decode(&[true; 728]), None);
+        assert_eq!(Cnf::new(3).solve().and_then(|model| decode(&model)), None);
+    }
+}
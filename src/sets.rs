@@ -1,12 +1,18 @@
 // use std::collections::HashSet;
 use std::fmt;
+use crate::solver::{self, SolveOutcome};
+use crate::error::{self, SudokuError};
+use crate::regions::Regions;
+use crate::rules::{ExtraPeers, Rules};
 use std::collections::BTreeSet as Set;
 #[derive(Debug)]
 pub struct Sudoku {
     grid: [[u8; 9]; 9],
     r_sets: [Set<u8>; 9],
     c_sets: [Set<u8>; 9],
-    z_sets: [[Set<u8>; 3]; 3],
+    z_sets: [Set<u8>; 9],
+    regions: Regions,
+    extra_peers: ExtraPeers,
 }
 
 #[allow(clippy::new_without_default)]
@@ -16,9 +22,7 @@ impl Sudoku {
             .try_into().unwrap();
         let c_sets = vec![Sudoku::init_set(); 9]
             .try_into().unwrap();
-        let z_sets: [[Set<u8>; 3]; 3] = vec![vec![Sudoku::init_set(); 3]; 3]
-            .into_iter().map(|v| v.try_into().unwrap())
-            .collect::<Vec<_>>()
+        let z_sets = vec![Sudoku::init_set(); 9]
             .try_into().unwrap();
 
         Sudoku {
@@ -26,10 +30,12 @@ impl Sudoku {
             r_sets,
             c_sets,
             z_sets,
+            regions: Regions::standard(),
+            extra_peers: ExtraPeers::default(),
         }
     }
 
-    pub fn from(grid: [[u8; 9]; 9]) -> Result<Sudoku, &'static str> {
+    pub fn from(grid: [[u8; 9]; 9]) -> Result<Sudoku, SudokuError> {
         let mut new_sudoku = Sudoku {
             grid: [[0u8; 9]; 9],
             ..Sudoku::new()
@@ -38,58 +44,76 @@ impl Sudoku {
         Ok(new_sudoku)
     }
 
-    pub fn set(&mut self, r: usize, c: usize, n: u8) -> Result<bool, &'static str> {
+    pub fn from_rules(grid: [[u8; 9]; 9], rules: &Rules) -> Result<Sudoku, SudokuError> {
+        let mut new_sudoku = Sudoku {
+            regions: rules.regions().clone(),
+            extra_peers: rules.all_extra_peers().clone(),
+            ..Sudoku::new()
+        };
+        new_sudoku.set_grid(grid)?;
+        Ok(new_sudoku)
+    }
+
+    pub fn set(&mut self, r: usize, c: usize, n: u8) -> Result<bool, SudokuError> {
         if n == 0 { self.clear(r, c)?; return Ok(true); }
-        if !(1..=9).contains(&n) { return Err("Value is not in range 1..=9, was {n}"); }
+        Sudoku::restrain_index(r, c)?;
+        if !(1..=9).contains(&n) { return Err(SudokuError::ValueOutOfRange { row: r, col: c, value: n, max: 9 }); }
         if !self.is_valid(r, c, n)? { return Ok(false); }
         self.grid[r][c] = n;
         self.r_sets[r].remove(&n);
         self.c_sets[c].remove(&n);
-        self.z_sets[r / 3][c / 3].remove(&n);
+        self.z_sets[self.regions.region(r, c)].remove(&n);
         
         Ok(true)
     }
 
-    pub fn get(&self, r: usize, c: usize) -> Result<u8, &'static str> {
+    pub fn get(&self, r: usize, c: usize) -> Result<u8, SudokuError> {
         Sudoku::restrain_index(r, c)?;
         Ok(self.grid[r][c])
     }
 
-    pub fn clear(&mut self, r: usize, c: usize) -> Result<(), &'static str> {
+    pub fn clear(&mut self, r: usize, c: usize) -> Result<(), SudokuError> {
         let n = self.get(r, c)?;
         if n == 0 { return Ok(()); }
         self.r_sets[r].insert(n);
         self.c_sets[c].insert(n);
-        self.z_sets[r / 3][c / 3].insert(n);
+        self.z_sets[self.regions.region(r, c)].insert(n);
         self.grid[r][c] = 0;
         Ok(())
     }
 
     pub fn clear_all(&mut self) {
-        *self = Sudoku::new();
+        let regions = self.regions.clone();
+        let extra_peers = std::mem::take(&mut self.extra_peers);
+        *self = Sudoku { regions, extra_peers, ..Sudoku::new() };
     }
 
     #[allow(clippy::needless_range_loop)]
-    pub fn set_grid(&mut self, grid: [[u8; 9]; 9]) -> Result<bool, &'static str> {
+    pub fn set_grid(&mut self, grid: [[u8; 9]; 9]) -> Result<(), SudokuError> {
+        error::validate_with(&grid, |a, b| {
+            self.regions.sees(a, b) || self.extra_peers.of(a.0, a.1).contains(&b)
+        })?;
         self.clear_all();
         for i in 0..9 {
             for j in 0..9 {
-                if !self.set(i, j, grid[i][j])? { return Ok(false); }
+                self.set(i, j, grid[i][j])?;
             }
         }
-        Ok(true)
+        Ok(())
     }
     
     fn init_set() -> Set<u8> {
         (1..=9).collect()
     }
     
-    fn is_valid(&self, r: usize, c: usize, n: u8) -> Result<bool, &'static str> {
+
+    fn is_valid(&self, r: usize, c: usize, n: u8) -> Result<bool, SudokuError> {
         Sudoku::restrain_index(r, c)?;
         Ok(
             self.r_sets[r].contains(&n) &&
             self.c_sets[c].contains(&n) &&
-            self.z_sets[r / 3][c / 3].contains(&n)
+            self.z_sets[self.regions.region(r, c)].contains(&n) &&
+            self.extra_peers.of(r, c).iter().all(|&(rr, cc)| self.grid[rr][cc] != n)
         )
     }
 
@@ -118,9 +142,13 @@ impl Sudoku {
         false
     }
 
-    fn restrain_index(r: usize, c: usize) -> Result<(), &'static str> {
+    fn restrain_index(r: usize, c: usize) -> Result<(), SudokuError> {
         if (0..9).contains(&r) && (0..9).contains(&c) { Ok(()) }
-        else { Err("Index greater than 9") }
+        else { Err(SudokuError::IndexOutOfRange { row: r, col: c, size: 9 }) }
+    }
+
+    pub fn grid(&self) -> &[[u8; 9]; 9] {
+        &self.grid
     }
 
     pub fn print_sets(&self) {
@@ -133,14 +161,39 @@ impl Sudoku {
             println!("{:?}", col);
         }
         println!("Zone vacant: ");
-        for row in &self.z_sets {
-            for zone in row {
-                println!("{:?}", zone);
-            }
+        for zone in &self.z_sets {
+            println!("{:?}", zone);
         }
     }
 }
 
+pub struct Sets;
+
+impl solver::Solver for Sets {
+    fn name(&self) -> &'static str {
+        "backtracking + sets"
+    }
+
+    fn solve(&self, grid: &[[u8; 9]; 9]) -> SolveOutcome {
+        let mut sudoku = match Sudoku::from(*grid) {
+            Ok(sudoku) => sudoku,
+            Err(err) => return SolveOutcome::Invalid(err),
+        };
+        if sudoku.solve() { SolveOutcome::Solved(*sudoku.grid()) }
+        else { SolveOutcome::Unsolvable }
+    }
+
+    fn solve_with(&self, grid: &[[u8; 9]; 9], rules: &Rules) -> SolveOutcome {
+        if !self.supports(rules) { return solver::UNSUPPORTED; }
+        let mut sudoku = match Sudoku::from_rules(*grid, rules) {
+            Ok(sudoku) => sudoku,
+            Err(err) => return SolveOutcome::Invalid(err),
+        };
+        if sudoku.solve() { SolveOutcome::Solved(*sudoku.grid()) }
+        else { SolveOutcome::Unsolvable }
+    }
+}
+
 impl fmt::Display for Sudoku {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut s = String::new();
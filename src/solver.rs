@@ -0,0 +1,197 @@
+use crate::{backtrack, sets, bits, wavefunc, dlx, sat};
+use crate::error::SudokuError;
+use crate::rules::Rules;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SolveOutcome {
+    Solved([[u8; 9]; 9]),
+    Unsolvable,
+    Invalid(SudokuError),
+}
+
+impl SolveOutcome {
+    pub fn is_solved(&self) -> bool {
+        matches!(self, SolveOutcome::Solved(_))
+    }
+
+    pub fn solution(&self) -> Option<[[u8; 9]; 9]> {
+        match self {
+            SolveOutcome::Solved(grid) => Some(*grid),
+            _ => None,
+        }
+    }
+}
+
+// A solving strategy. `Invalid` is returned when the clues themselves are
+// malformed (a digit outside 0..=9 or two equal digits sharing a house),
+// `Unsolvable` when the clues are consistent but admit no solution.
+pub trait Solver {
+    fn name(&self) -> &'static str;
+    fn solve(&self, grid: &[[u8; 9]; 9]) -> SolveOutcome;
+
+    // Solves under variant rules. Rules a solver doesn't `support` make the
+    // grid `Invalid`.
+    fn solve_with(&self, grid: &[[u8; 9]; 9], rules: &Rules) -> SolveOutcome;
+
+    // Every solver handles any layout of regions and extra houses, but only
+    // some handle constraints beyond that.
+    fn supports(&self, rules: &Rules) -> bool {
+        !rules.has_constraints()
+    }
+}
+
+pub(crate) const UNSUPPORTED: SolveOutcome = SolveOutcome::Invalid(
+    SudokuError::InvalidConstraint { reason: "the solver does not support these rules" }
+);
+
+pub fn all() -> Vec<Box<dyn Solver>> {
+    vec![
+        Box::new(backtrack::Backtrack),
+        Box::new(sets::Sets),
+        Box::new(bits::Bits),
+        Box::new(wavefunc::WaveFunc),
+        Box::new(dlx::Dlx),
+        Box::new(sat::Sat),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regions::Regions;
+    use crate::test_util::SPARSE;
+
+    #[test]
+    fn solvers_agree() {
+        let outcomes: Vec<SolveOutcome> = all().iter()
+            .map(|solver| solver.solve(&SPARSE))
+            .collect();
+        assert!(outcomes[0].is_solved());
+        assert!(outcomes.iter().all(|outcome| *outcome == outcomes[0]));
+    }
+
+    #[test]
+    fn conflicting_clues_are_invalid() {
+        let mut grid = SPARSE;
+        grid[0][0] = 8;
+        for solver in all() {
+            assert_eq!(
+                solver.solve(&grid),
+                SolveOutcome::Invalid(SudokuError::Conflict { row: 0, col: 2, value: 8, with: (0, 0) }),
+                "{}", solver.name()
+            );
+        }
+    }
+
+    #[test]
+    fn solvers_follow_jigsaw_regions() {
+        let regions = Regions::parse("
+            AAABBBCCC
+            AAABBBCCC
+            AADBBBCCC
+            ADDEEEFFF
+            DDDEEEFFF
+            DDDEEEFFF
+            GGGHHHIII
+            GGGHHHIII
+            GGGHHHIII
+        ").unwrap();
+        let rules = Rules::jigsaw(regions);
+        let mut grid = [[0u8; 9]; 9];
+        grid[0] = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+        for solver in all() {
+            let solution = solver.solve_with(&grid, &rules).solution().unwrap();
+            assert_eq!(solution[0], grid[0], "{}", solver.name());
+            assert!(rules.validate(&solution).is_ok(), "{}", solver.name());
+            assert!(solution.iter().flatten().all(|&n| n != 0), "{}", solver.name());
+        }
+
+        // (2, 2) and (3, 0) swapped regions, so they now see each other
+        grid[3][0] = 3;
+        for solver in all() {
+            assert_eq!(
+                solver.solve_with(&grid, &rules),
+                SolveOutcome::Invalid(SudokuError::Conflict { row: 3, col: 0, value: 3, with: (0, 2) }),
+                "{}", solver.name()
+            );
+        }
+    }
+
+    #[test]
+    fn solvers_follow_extra_houses() {
+        let variants: [fn(&mut Rules); 3] = [Rules::add_diagonals, Rules::add_windoku, Rules::add_centre_dot];
+        for add_houses in variants {
+            let mut rules = Rules::standard();
+            add_houses(&mut rules);
+            let mut grid = [[0u8; 9]; 9];
+            grid[4][4] = 5;
+            for solver in all() {
+                let solution = solver.solve_with(&grid, &rules).solution().unwrap();
+                assert!(rules.validate(&solution).is_ok(), "{}", solver.name());
+                for house in rules.houses() {
+                    let mut values: Vec<u8> = house.iter().map(|&(r, c)| solution[r][c]).collect();
+                    values.sort();
+                    assert_eq!(values, [1, 2, 3, 4, 5, 6, 7, 8, 9], "{}", solver.name());
+                }
+            }
+        }
+
+        let mut rules = Rules::standard();
+        rules.add_diagonals();
+        let mut grid = [[0u8; 9]; 9];
+        grid[0][0] = 5;
+        grid[8][8] = 5;
+        for solver in all() {
+            assert_eq!(
+                solver.solve_with(&grid, &rules),
+                SolveOutcome::Invalid(SudokuError::Conflict { row: 8, col: 8, value: 5, with: (0, 0) }),
+                "{}", solver.name()
+            );
+        }
+    }
+
+    #[test]
+    fn solvers_follow_chess_moves() {
+        // moves down the grid, to either side
+        let knight: &[(usize, usize)] = &[(1, 2), (2, 1)];
+        let king: &[(usize, usize)] = &[(1, 1)];
+        for moves in [knight, king] {
+            let mut rules = Rules::standard();
+            if moves == knight { rules.add_anti_knight(); } else { rules.add_anti_king(); }
+            let mut grid = [[0u8; 9]; 9];
+            grid[0] = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+            for solver in all() {
+                let solution = solver.solve_with(&grid, &rules).solution().unwrap();
+                assert!(rules.validate(&solution).is_ok(), "{}", solver.name());
+                for r in 0..8 {
+                    for c in 0..9 {
+                        for &(dr, dc) in moves {
+                            if r + dr < 9 && c + dc < 9 { assert_ne!(solution[r][c], solution[r + dr][c + dc]); }
+                            if r + dr < 9 && c >= dc { assert_ne!(solution[r][c], solution[r + dr][c - dc]); }
+                        }
+                    }
+                }
+            }
+
+            let mut grid = [[0u8; 9]; 9];
+            grid[4][4] = 5;
+            grid[4 + moves[0].0][4 + moves[0].1] = 5;
+            for solver in all() {
+                assert!(matches!(solver.solve_with(&grid, &rules), SolveOutcome::Invalid(SudokuError::Conflict { .. })));
+            }
+        }
+    }
+
+    #[test]
+    fn out_of_range_clue_is_invalid() {
+        let mut grid = SPARSE;
+        grid[4][4] = 10;
+        for solver in all() {
+            assert_eq!(
+                solver.solve(&grid),
+                SolveOutcome::Invalid(SudokuError::ValueOutOfRange { row: 4, col: 4, value: 10, max: 9 }),
+                "{}", solver.name()
+            );
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use crate::rules::{Constraint, Rules};
+use crate::wavefunc::Sudoku;
+
+// The classic 53..7.... puzzle, which singles alone solve, and the same
+// on one line.
+pub(crate) const EASY: [[u8; 9]; 9] = [
+    [5, 3, 0,  0, 7, 0,  0, 0, 0],
+    [6, 0, 0,  1, 9, 5,  0, 0, 0],
+    [0, 9, 8,  0, 0, 0,  0, 6, 0],
+
+    [8, 0, 0,  0, 6, 0,  0, 0, 3],
+    [4, 0, 0,  8, 0, 3,  0, 0, 1],
+    [7, 0, 0,  0, 2, 0,  0, 0, 6],
+
+    [0, 6, 0,  0, 0, 0,  2, 8, 0],
+    [0, 0, 0,  4, 1, 9,  0, 0, 5],
+    [0, 0, 0,  0, 8, 0,  0, 7, 9],
+];
+
+pub(crate) const EASY_LINE: &str = "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+
+// The solution of EASY, shared by tests across the crate.
+pub(crate) const SOLUTION: [[u8; 9]; 9] = [
+    [5, 3, 4,  6, 7, 8,  9, 1, 2],
+    [6, 7, 2,  1, 9, 5,  3, 4, 8],
+    [1, 9, 8,  3, 4, 2,  5, 6, 7],
+
+    [8, 5, 9,  7, 6, 1,  4, 2, 3],
+    [4, 2, 6,  8, 5, 3,  7, 9, 1],
+    [7, 1, 3,  9, 2, 4,  8, 5, 6],
+
+    [9, 6, 1,  5, 3, 7,  2, 8, 4],
+    [2, 8, 7,  4, 1, 9,  6, 3, 5],
+    [3, 4, 5,  2, 8, 6,  1, 7, 9],
+];
+
+// A sparser puzzle with a single solution, which takes the solvers some
+// searching.
+pub(crate) const SPARSE: [[u8; 9]; 9] = [
+    [0, 0, 8,  0, 0, 9,  0, 6, 2],
+    [0, 0, 0,  0, 0, 0,  0, 0, 5],
+    [1, 0, 2,  5, 0, 0,  0, 0, 0],
+
+    [0, 0, 0,  2, 1, 0,  0, 9, 0],
+    [0, 5, 0,  0, 0, 0,  6, 0, 0],
+    [6, 0, 0,  0, 0, 0,  0, 2, 8],
+
+    [4, 1, 0,  6, 0, 8,  0, 0, 0],
+    [8, 6, 0,  0, 3, 0,  1, 0, 0],
+    [0, 0, 0,  0, 0, 0,  4, 0, 0],
+];
+
+// `grid` under the standard rules and `constraints`, pruned as far as they go.
+pub(crate) fn pruned<C: Constraint + 'static>(constraints: impl IntoIterator<Item = C>, grid: [[u8; 9]; 9]) -> Sudoku {
+    let mut rules = Rules::standard();
+    for constraint in constraints {
+        rules.add(constraint);
+    }
+    Sudoku::from_rules(grid, &rules).unwrap()
+}
@@ -1,110 +1,123 @@
 use std::{collections::HashSet, fmt};
 use colored::Colorize;
+use crate::solver::{self, SolveOutcome};
+use crate::error::{self, SudokuError};
+use crate::rules::Rules;
 
 #[derive(Clone, Copy)]
 #[derive(Debug)]
 #[derive(PartialEq)]
-pub struct BitSet {
-    set: u16, // the unsigned int containing the numbers
-    size: u8, // how many numbers are currently contained in the set?
+pub struct BitSetN<const N: usize> {
+    set: u32, // the unsigned int containing the numbers
 }
-impl BitSet {
+
+pub type BitSet = BitSetN<9>;
+
+impl<const N: usize> BitSetN<N> {
+    const FITS: () = assert!(N < 32, "values must fit in a u32");
+
     pub fn contains(&self, x: u8) -> bool {
-        if !(1..=9).contains(&x) { return false; }
+        if !(1..=N).contains(&(x as usize)) { return false; }
         (self.set & (1 << x)) != 0
     }
     
-    pub fn insert(&self, x: u8) -> BitSet {
-        let new_size = {
-            if !self.contains(x) { self.size + 1 }
-            else { self.size }
-        };
-        BitSet {
-            set: self.set | 1 << x,
-            size: new_size,
-        }
+    pub fn insert(&self, x: u8) -> BitSetN<N> {
+        if !(1..=N).contains(&(x as usize)) { return *self; }
+        BitSetN { set: self.set | 1 << x }
     }
 
-    pub fn remove(&self, x: u8) -> BitSet {
-        let new_size = {
-            if self.contains(x) { self.size - 1 }
-            else { self.size }
-        };
-        BitSet {
-            set: self.set & !(1 << x),
-            size: new_size,
-        }
+    pub fn remove(&self, x: u8) -> BitSetN<N> {
+        if !(1..=N).contains(&(x as usize)) { return *self; }
+        BitSetN { set: self.set & !(1 << x) }
     }
     
     #[allow(clippy::new_without_default)]
-    pub fn new() -> BitSet {
-        BitSet {
-            set: 0u16,
-            size: 0u8,
-        }
+    pub fn new() -> BitSetN<N> {
+        BitSetN { set: 0u32 }
     }
 
-    pub fn full() -> BitSet {
-        BitSet {
-            set: 0b1111111110u16,
-            size: 9u8,
-        }
+    pub fn full() -> BitSetN<N> {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::FITS;
+        BitSetN { set: (u32::MAX >> (31 - N)) & !1 } // bits 1..=N
     }
 
-    pub fn from_single(x: u8) -> BitSet {
-        let set = BitSet::new();
+    pub fn from_single(x: u8) -> BitSetN<N> {
+        let set = BitSetN::new();
         set.insert(x)
     }
 
     pub fn is_empty(&self) -> bool {
-        self.size == 0
+        self.set == 0
     }
 
+    // how many numbers are currently contained in the set
     pub fn size(&self) -> u8 {
-        self.size
+        self.set.count_ones() as u8
+    }
+
+    pub fn union(&self, other: BitSetN<N>) -> BitSetN<N> {
+        BitSetN { set: self.set | other.set }
+    }
+
+    pub fn intersection(&self, other: BitSetN<N>) -> BitSetN<N> {
+        BitSetN { set: self.set & other.set }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = u8> {
+        let set = *self;
+        (1..=N as u8).filter(move |x| set.contains(*x))
     }
 }
 
-#[derive(Debug, Copy, Clone)]
-pub struct Sudoku {
-    grid: [[Cell; 9]; 9],
+// An N x N grid split into boxes of BH rows by BW columns, so N = BH * BW.
+// The classic grid is `Sudoku`; the other common sizes have aliases below.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SudokuN<const N: usize, const BH: usize, const BW: usize> {
+    grid: [[CellN<N>; N]; N],
 }
 
-trait CellIter = Iterator<Item = (Cell, usize, usize)>;
+pub type Sudoku = SudokuN<9, 3, 3>;
+pub type Sudoku4 = SudokuN<4, 2, 2>;
+pub type Sudoku6 = SudokuN<6, 2, 3>;
+pub type Sudoku12 = SudokuN<12, 3, 4>;
+pub type Sudoku16 = SudokuN<16, 4, 4>;
+pub type Sudoku25 = SudokuN<25, 5, 5>;
 
-impl Sudoku {
-    pub fn solved(&self) -> Option<Sudoku> {
-        match self.lowest_entropy() {
-            _ if !self.is_valid() => None,
-            None => Some(*self),
-            Some((cell, min_row, min_col)) => {
-                let possible_values_set = cell.possible_values();
-                for val in 1..=9 {
-                    if !possible_values_set.contains(val) { continue; }
-                    let updated_grid = self.set(min_row, min_col, val);
-                    // updated_grid.print_affected_cells(min_row, min_col);
-                    // println!("\n");
-
-                    match updated_grid.solved() {
-                        solution@Some(_) => return solution,
-                        None => { continue; },
-                    }
-                }
-                None
-            },
-        }
+trait CellIter<const N: usize> = Iterator<Item = (CellN<N>, usize, usize)>;
+
+impl<const N: usize, const BH: usize, const BW: usize> SudokuN<N, BH, BW> {
+    const BOXES: () = assert!(BH * BW == N, "boxes must tile the grid");
+
+    // Searches with an explicit stack rather than recursion, which would
+    // overflow the stack at depth N * N for the larger grids.
+    pub fn solved(&self) -> Option<Self> {
+        self.solutions().next_solution()
     }
 
-    fn is_valid(&self) -> bool {
+    pub fn count_solutions(&self, limit: usize) -> usize {
+        self.solutions().take(limit).count()
+    }
+
+    pub fn solutions(&self) -> Solutions<'static, N, BH, BW> {
+        Solutions { propagation: &Standard, next: Some(*self), stack: Vec::new() }
+    }
+
+    pub fn is_unique(&self) -> bool {
+        self.count_solutions(2) == 1
+    }
+
+    pub fn is_valid(&self) -> bool {
         self.cells_with_indexes()
             .all(|(cell, _, _)| cell.is_valid())
     }
 
-    fn lowest_entropy(&self) -> Option<(Cell, usize, usize)> {
+    #[allow(clippy::match_like_matches_macro)]
+    fn lowest_entropy(&self) -> Option<(CellN<N>, usize, usize)> {
         self.cells_with_indexes()
             .filter(|(cell, _, _)| {
                 match cell {
-                    Cell::Unknown(_) => true,
+                    CellN::Unknown(_) => true,
                     _ => false,
                 }
             })
@@ -116,7 +129,7 @@ impl Sudoku {
             )
     }
 
-    fn cells_with_indexes(&self) -> impl CellIter {
+    fn cells_with_indexes(&self) -> impl CellIter<N> {
         self.grid.into_iter()
             .enumerate()
             .flat_map(|(row, cells)|
@@ -126,15 +139,15 @@ impl Sudoku {
             )
     }
 
-    fn affected_cells(&self, row: usize, col: usize) -> impl CellIter {
+    fn affected_cells(&self, row: usize, col: usize) -> impl CellIter<N> {
         self.cells_with_indexes()
             .filter(move |(_, r, c)| {
                 *r == row || *c == col
-                || (row / 3 == r / 3 && col / 3 == c / 3) 
+                || (row / BH == r / BH && col / BW == c / BW) 
             })
     }
 
-    pub fn set(&self, row: usize, col: usize, val: u8) -> Sudoku {
+    pub fn set(&self, row: usize, col: usize, val: u8) -> Self {
         let mut new_sudoku =
             self.updated(row, col, self.grid[row][col].set(val));
 
@@ -146,26 +159,54 @@ impl Sudoku {
         new_sudoku
     }
 
-    fn updated(&self, row: usize, col: usize, cell: Cell) -> Sudoku {
+    pub fn without(&self, row: usize, col: usize, val: u8) -> Self {
+        self.updated(row, col, self.grid[row][col].without(val))
+    }
+
+    pub fn cell(&self, row: usize, col: usize) -> CellN<N> {
+        self.grid[row][col]
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.is_valid() && self.lowest_entropy().is_none()
+    }
+
+    fn updated(&self, row: usize, col: usize, cell: CellN<N>) -> Self {
         let mut grid = self.grid;
         grid[row][col] = cell;
-        Sudoku { grid }
+        SudokuN { grid }
     }
 
-    pub fn empty() -> Sudoku {
-        let grid = [[Cell::new(); 9]; 9];
-        Sudoku { grid }
+    pub fn empty() -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::BOXES;
+        let grid = [[CellN::new(); N]; N];
+        SudokuN { grid }
     }
 
-    pub fn from(grid: [[u8; 9]; 9]) -> Sudoku {
-        let mut sudoku = Sudoku::empty();
+    pub fn from(grid: [[u8; N]; N]) -> Result<Self, SudokuError> {
+        error::validate_boxed(&grid, BH, BW)?;
+        let mut sudoku = Self::empty();
         for (row_idx, row) in grid.into_iter().enumerate() {
             for (col_idx, num) in row.into_iter().enumerate() {
                 if num == 0 { continue; }
                 sudoku = sudoku.set(row_idx, col_idx, num);
             }
         }
-        sudoku
+        Ok(sudoku)
+    }
+
+    // Takes the cells as they are, without placing their values or checking
+    // them against each other, as when reading back a grid of candidates.
+    pub fn from_cells(grid: [[CellN<N>; N]; N]) -> Self {
+        SudokuN { grid }
+    }
+
+    pub fn grid(&self) -> [[u8; N]; N] {
+        self.grid.map(|row| row.map(|cell| match cell {
+            CellN::Known(val) => val,
+            _ => 0,
+        }))
     }
 
     pub fn print_affected_cells(&self, row: usize, col: usize) {
@@ -174,8 +215,8 @@ impl Sudoku {
                 .map(|(_, r, c)| (r, c))
         );
         
-        for row in 0..9 {
-            for col in 0..9 {
+        for row in 0..N {
+            for col in 0..N {
                 let text = 
                     if affected_cells.contains(&(row, col)) {
                         self.grid[row][col].to_string().reversed()
@@ -183,24 +224,170 @@ impl Sudoku {
                         self.grid[row][col].to_string().normal()
                     };
                 print!("{text} ");
-                if col % 3 == 2 { print!(" "); }
+                if col % BW == BW - 1 { print!(" "); }
             }
             println!();
-            if row % 3 == 2 { println!() }
+            if row % BH == BH - 1 { println!() }
+        }
+    }
+}
+
+// Depth-first search that always branches on the cell with the fewest
+// candidates, trying them in increasing order. The stack of branch points
+// lets it be resumed between solutions.
+pub struct Solutions<'a, const N: usize, const BH: usize, const BW: usize> {
+    propagation: &'a dyn Propagation<N, BH, BW>,
+    next: Option<SudokuN<N, BH, BW>>,
+    stack: Vec<(SudokuN<N, BH, BW>, usize, usize, BitSetN<N>)>,
+}
+
+impl<const N: usize, const BH: usize, const BW: usize> Solutions<'_, N, BH, BW> {
+    fn next_solution(&mut self) -> Option<SudokuN<N, BH, BW>> {
+        loop {
+            let sudoku = match self.next.take() {
+                Some(sudoku) => sudoku,
+                None => {
+                    let (sudoku, row, col, remaining) = self.stack.last_mut()?;
+                    match remaining.iter().next() {
+                        Some(val) => {
+                            *remaining = remaining.remove(val);
+                            self.propagation.place(sudoku, *row, *col, val)
+                        },
+                        None => { self.stack.pop(); continue; },
+                    }
+                },
+            };
+            match sudoku.lowest_entropy() {
+                _ if !self.propagation.is_valid(&sudoku) => continue,
+                None => return Some(sudoku),
+                Some((cell, min_row, min_col)) =>
+                    self.stack.push((sudoku, min_row, min_col, cell.possible_values())),
+            }
         }
     }
 }
 
-impl fmt::Display for Sudoku {
+impl<const N: usize, const BH: usize, const BW: usize> Iterator for Solutions<'_, N, BH, BW> {
+    type Item = [[u8; N]; N];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_solution().map(|sudoku| sudoku.grid())
+    }
+}
+
+// How the search places a value and judges the result: the standard rules
+// for any size, or a set of `Rules` for the 9x9 variants.
+trait Propagation<const N: usize, const BH: usize, const BW: usize> {
+    fn place(&self, sudoku: &SudokuN<N, BH, BW>, row: usize, col: usize, val: u8) -> SudokuN<N, BH, BW>;
+    fn is_valid(&self, sudoku: &SudokuN<N, BH, BW>) -> bool;
+}
+
+struct Standard;
+
+impl<const N: usize, const BH: usize, const BW: usize> Propagation<N, BH, BW> for Standard {
+    fn place(&self, sudoku: &SudokuN<N, BH, BW>, row: usize, col: usize, val: u8) -> SudokuN<N, BH, BW> {
+        sudoku.set(row, col, val)
+    }
+
+    fn is_valid(&self, sudoku: &SudokuN<N, BH, BW>) -> bool {
+        sudoku.is_valid()
+    }
+}
+
+impl Propagation<9, 3, 3> for Rules {
+    fn place(&self, sudoku: &Sudoku, row: usize, col: usize, val: u8) -> Sudoku {
+        sudoku.set_with(row, col, val, self)
+    }
+
+    fn is_valid(&self, sudoku: &Sudoku) -> bool {
+        self.is_valid(sudoku)
+    }
+}
+
+// Solving under variant rules, where the peers of a cell and any extra
+// candidate eliminations come from `Rules` instead of the fixed geometry.
+impl Sudoku {
+    pub fn from_rules(grid: [[u8; 9]; 9], rules: &Rules) -> Result<Sudoku, SudokuError> {
+        rules.validate(&grid)?;
+        let mut sudoku = Sudoku::empty();
+        for (row_idx, row) in grid.into_iter().enumerate() {
+            for (col_idx, num) in row.into_iter().enumerate() {
+                if num == 0 { continue; }
+                sudoku = sudoku.place_with(row_idx, col_idx, num, rules);
+            }
+        }
+        Ok(rules.prune(&sudoku))
+    }
+
+    pub fn set_with(&self, row: usize, col: usize, val: u8, rules: &Rules) -> Sudoku {
+        rules.prune(&self.place_with(row, col, val, rules))
+    }
+
+    fn place_with(&self, row: usize, col: usize, val: u8, rules: &Rules) -> Sudoku {
+        let mut new_sudoku =
+            self.updated(row, col, self.grid[row][col].set(val));
+
+        for &(r, c) in rules.peers(row, col) {
+            new_sudoku =
+                new_sudoku.updated(r, c, new_sudoku.grid[r][c].without(val));
+        }
+
+        new_sudoku
+    }
+
+    pub fn solved_with(&self, rules: &Rules) -> Option<Sudoku> {
+        self.solutions_with(rules).next_solution()
+    }
+
+    pub fn solutions_with<'a>(&self, rules: &'a Rules) -> Solutions<'a, 9, 3, 3> {
+        Solutions { propagation: rules, next: Some(*self), stack: Vec::new() }
+    }
+}
+
+pub struct WaveFunc;
+
+impl solver::Solver for WaveFunc {
+    fn name(&self) -> &'static str {
+        "wavefunc"
+    }
+
+    fn solve(&self, grid: &[[u8; 9]; 9]) -> SolveOutcome {
+        let sudoku = match Sudoku::from(*grid) {
+            Ok(sudoku) => sudoku,
+            Err(err) => return SolveOutcome::Invalid(err),
+        };
+        match sudoku.solved() {
+            Some(solution) => SolveOutcome::Solved(solution.grid()),
+            None => SolveOutcome::Unsolvable,
+        }
+    }
+
+    fn solve_with(&self, grid: &[[u8; 9]; 9], rules: &Rules) -> SolveOutcome {
+        let sudoku = match Sudoku::from_rules(*grid, rules) {
+            Ok(sudoku) => sudoku,
+            Err(err) => return SolveOutcome::Invalid(err),
+        };
+        match sudoku.solved_with(rules) {
+            Some(solution) => SolveOutcome::Solved(solution.grid()),
+            None => SolveOutcome::Unsolvable,
+        }
+    }
+
+    fn supports(&self, _rules: &Rules) -> bool {
+        true
+    }
+}
+
+impl<const N: usize, const BH: usize, const BW: usize> fmt::Display for SudokuN<N, BH, BW> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut s = String::new();
-        for row in 0..9 {
-            for col in 0..9 {
+        for row in 0..N {
+            for col in 0..N {
                 s.push_str(&format!("{} ", self.grid[row][col]));
-                if col % 3 == 2 { s.push(' ')}
+                if col % BW == BW - 1 { s.push(' ')}
             }
             s.push('\n');
-            if row % 3 == 2 { s.push('\n'); }
+            if row % BH == BH - 1 { s.push('\n'); }
         }
         write!(f, "{s}")
     }
@@ -209,70 +396,75 @@ impl fmt::Display for Sudoku {
 #[derive(Copy, Clone)]
 #[derive(Debug)]
 #[derive(PartialEq)]
-pub enum Cell {
+pub enum CellN<const N: usize> {
     Known(u8),
-    Unknown(BitSet),
+    Unknown(BitSetN<N>),
     Invalid(Option<u8>)
 }
 
-impl Cell {
+pub type Cell = CellN<9>;
+
+impl<const N: usize> CellN<N> {
+    #[allow(clippy::match_like_matches_macro)]
     fn is_valid(&self) -> bool {
         match self {
-            Cell::Invalid(_) => false,
+            CellN::Invalid(_) => false,
             _ => true,
         }
     }
 
     fn set(&self, val: u8) -> Self {
         match self {
-            Cell::Known(_) => *self,
-            Cell::Unknown(values) => {
-                if values.contains(val) { Cell::Known(val) }
-                else { Cell::Invalid(Some(val)) }
+            CellN::Known(_) => *self,
+            CellN::Unknown(values) => {
+                if values.contains(val) { CellN::Known(val) }
+                else { CellN::Invalid(Some(val)) }
             },
-            Cell::Invalid(_) => *self,
+            CellN::Invalid(_) => *self,
         }
     }
 
     fn without(&self, val: u8) -> Self {
         match self {
-            Cell::Known(_) => *self,
-            Cell::Unknown(values) => {
-                let new_values: BitSet = values.remove(val);
-                if new_values.is_empty() { Cell::Invalid(None) }
-                else { Cell::Unknown(new_values) }
+            CellN::Known(_) => *self,
+            CellN::Unknown(values) => {
+                let new_values: BitSetN<N> = values.remove(val);
+                if new_values.is_empty() { CellN::Invalid(None) }
+                else { CellN::Unknown(new_values) }
             },
-            Cell::Invalid(_) => *self,
+            CellN::Invalid(_) => *self,
         }
     }
 
-    fn possible_values(&self) -> BitSet {
+    pub fn possible_values(&self) -> BitSetN<N> {
         match self {
-            Cell::Known(val) => BitSet::from_single(*val),
-            Cell::Unknown(values) => *values,
-            Cell::Invalid(_) => BitSet::new(),
+            CellN::Known(val) => BitSetN::from_single(*val),
+            CellN::Unknown(values) => *values,
+            CellN::Invalid(_) => BitSetN::new(),
         }
     }
 
-    fn default_values() -> BitSet {
-        BitSet::full()
+    fn default_values() -> BitSetN<N> {
+        BitSetN::full()
     }
 
-    fn new() -> Cell {
-        Cell::Unknown(Cell::default_values())
+    fn new() -> Self {
+        CellN::Unknown(CellN::default_values())
     }
 }
 
-impl fmt::Display for Cell {
+impl<const N: usize> fmt::Display for CellN<N> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        use Cell::*;
+        use CellN::*;
+        // pad so that columns line up once values take two digits
+        let width = N.to_string().len();
         match self {
             Known(val) => {
-                write!(f, "{}", val.to_string().green())
+                write!(f, "{}", format!("{val:>width$}").green())
             },
-            Unknown(_) => write!(f, "-"),
-            Invalid(Some(val)) => write!(f, "{}", val.to_string().red()),
-            Invalid(None) => write!(f, "{}", "X".red()),
+            Unknown(_) => write!(f, "{:>width$}", "-"),
+            Invalid(Some(val)) => write!(f, "{}", format!("{val:>width$}").red()),
+            Invalid(None) => write!(f, "{}", format!("{:>width$}", "X").red()),
         }
     }
 }
@@ -280,6 +472,7 @@ impl fmt::Display for Cell {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::SPARSE;
 
     #[test]
     fn initial_set() {
@@ -303,6 +496,69 @@ mod tests {
         println!("{cell}");
     }
 
+    #[test]
+    fn unique_puzzle() {
+        let sudoku = Sudoku::from(SPARSE).unwrap();
+        assert_eq!(sudoku.count_solutions(10), 1);
+        assert!(sudoku.is_unique());
+    }
+
+    #[test]
+    fn ambiguous_puzzle() {
+        let mut grid = SPARSE;
+        grid[0][2] = 0;
+        grid[3][3] = 0;
+        grid[7][4] = 0;
+        let sudoku = Sudoku::from(grid).unwrap();
+        assert_eq!(sudoku.count_solutions(2), 2);
+        assert!(!sudoku.is_unique());
+        assert_eq!(Sudoku::empty().count_solutions(4), 4);
+    }
+
+    #[test]
+    fn solutions_are_lazy_and_distinct() {
+        let mut grid = SPARSE;
+        grid[0][2] = 0;
+        grid[3][3] = 0;
+        grid[7][4] = 0;
+        let sudoku = Sudoku::from(grid).unwrap();
+        let solutions: Vec<_> = sudoku.solutions().take(20).collect();
+        assert!(solutions.len() >= 2);
+        assert_eq!(solutions[0], sudoku.solved().unwrap().grid());
+        assert_eq!(solutions.len(), sudoku.count_solutions(20));
+        assert_eq!(solutions.iter().collect::<HashSet<_>>().len(), solutions.len());
+
+        let first_ten: Vec<_> = Sudoku::empty().solutions().take(10).collect();
+        assert_eq!(first_ten.len(), 10);
+        assert_eq!(first_ten.iter().collect::<HashSet<_>>().len(), 10);
+    }
+
+    fn is_solution<const N: usize>(grid: &[[u8; N]; N], box_height: usize, box_width: usize) -> bool {
+        grid.iter().flatten().all(|&n| n != 0)
+            && error::validate_boxed(grid, box_height, box_width).is_ok()
+    }
+
+    #[test]
+    fn other_sizes() {
+        let solution = Sudoku4::empty().solved().unwrap().grid();
+        assert!(is_solution(&solution, 2, 2));
+
+        let mut grid = [[0u8; 6]; 6];
+        grid[0] = [1, 2, 3, 4, 5, 6];
+        let solution = Sudoku6::from(grid).unwrap().solved().unwrap().grid();
+        assert_eq!(solution[0], grid[0]);
+        assert!(is_solution(&solution, 2, 3));
+
+        let solution = Sudoku16::empty().solved().unwrap().grid();
+        assert!(is_solution(&solution, 4, 4));
+        assert_eq!(Sudoku16::from(solution).unwrap().count_solutions(2), 1);
+
+        let mut grid = [[0u8; 25]; 25];
+        grid[0] = std::array::from_fn(|i| 25 - i as u8);
+        let solution = Sudoku25::from(grid).unwrap().solved().unwrap().grid();
+        assert!(is_solution(&solution, 5, 5));
+    }
+
     #[test]
     fn cell_invalid_set() {
         let cell = Cell::new().without(1);